@@ -0,0 +1,144 @@
+//! Plays a clip's audio track alongside it, for checking timing against sound.
+//!
+//! Bevy's audio sinks can't seek, so the track is restarted whenever the clip
+//! (re)starts from the beginning: on switching clips, on every loop and on
+//! seeking back to the start. Seeking anywhere else stops the track until the
+//! next loop rather than letting it drift out of sync.
+
+use bevy::audio::AudioSinkPlayback;
+use bevy::prelude::*;
+
+use crate::{Animations, AnimationsMetadata};
+
+/// Largest difference between the expected and actual clip position that is
+/// still treated as normal playback rather than a seek.
+const SEEK_TOLERANCE: f32 = 0.05;
+
+#[derive(Component)]
+struct ClipAudio;
+
+/// Clip position and loop count last seen, and the speed and frame time they
+/// were about to advance by.
+struct PlaybackSample {
+    clip: AssetId<AnimationClip>,
+    time: f32,
+    completions: u32,
+    advance: f32,
+}
+
+pub struct AudioSyncPlugin;
+
+impl Plugin for AudioSyncPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (restart_clip_audio, follow_player_state)
+                .chain()
+                .run_if(resource_exists::<Animations>()),
+        );
+    }
+}
+
+fn restart_clip_audio(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    time: Res<Time>,
+    virtual_time: Res<Time<Virtual>>,
+    animations: Res<Animations>,
+    animation_meta: Res<AnimationsMetadata>,
+    players: Query<&AnimationPlayer>,
+    tracks: Query<Entity, With<ClipAudio>>,
+    mut previous: Local<Option<PlaybackSample>>,
+) {
+    let Some(player) = players.iter().next() else {
+        return;
+    };
+    let clip = player.animation_clip().id();
+    let current = player.seek_time();
+    let advance = if player.is_paused() {
+        0.0
+    } else {
+        time.delta_seconds() * player.speed()
+    };
+    let last = previous.replace(PlaybackSample {
+        clip,
+        time: current,
+        completions: player.completions(),
+        advance,
+    });
+
+    if let Some(last) = last {
+        let started_over = last.clip != clip || last.completions != player.completions();
+        let seeked = (current - (last.time + last.advance)).abs() > SEEK_TOLERANCE;
+        if !started_over && !seeked {
+            return;
+        }
+        if !started_over && current > SEEK_TOLERANCE {
+            if !tracks.is_empty() {
+                println!("audio: seeked away from the start, muted until the next loop");
+            }
+            for entity in &tracks {
+                commands.entity(entity).despawn();
+            }
+            return;
+        }
+    }
+
+    for entity in &tracks {
+        commands.entity(entity).despawn();
+    }
+    let Some(path) = animations
+        .0
+        .iter()
+        .position(|handle| handle.id() == clip)
+        .and_then(|index| animation_meta.0.get(index))
+        .and_then(|params| params.audio.as_deref())
+    else {
+        return;
+    };
+    let speed = track_speed(player, &virtual_time);
+    commands.spawn((
+        AudioBundle {
+            source: asset_server.load(path),
+            settings: PlaybackSettings {
+                paused: player.is_paused() || speed <= 0.0,
+                speed: speed.max(f32::EPSILON),
+                ..PlaybackSettings::ONCE
+            },
+        },
+        ClipAudio,
+    ));
+}
+
+/// Speed the track plays at to keep up with the clip: the player's speed
+/// scaled by the virtual clock, which slow motion slows down.
+fn track_speed(player: &AnimationPlayer, virtual_time: &Time<Virtual>) -> f32 {
+    player.speed() * virtual_time.relative_speed()
+}
+
+/// Mirrors pause and speed changes, of the player or the virtual clock, onto
+/// the playing track. Reverse playback pauses the track, since it can't be
+/// played backwards.
+fn follow_player_state(
+    virtual_time: Res<Time<Virtual>>,
+    players: Query<&AnimationPlayer>,
+    sinks: Query<&AudioSink, With<ClipAudio>>,
+) {
+    let Some(player) = players.iter().next() else {
+        return;
+    };
+    let speed = track_speed(player, &virtual_time);
+    for sink in &sinks {
+        let paused = player.is_paused() || speed <= 0.0;
+        if paused != sink.is_paused() {
+            if paused {
+                sink.pause();
+            } else {
+                sink.play();
+            }
+        }
+        if !paused && sink.speed() != speed {
+            sink.set_speed(speed);
+        }
+    }
+}
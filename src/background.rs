@@ -0,0 +1,196 @@
+//! What the scene camera shows behind the character.
+
+use bevy::asset::LoadState;
+use bevy::core_pipeline::Skybox;
+use bevy::pbr::{NotShadowCaster, NotShadowReceiver};
+use bevy::prelude::*;
+use bevy::render::mesh::VertexAttributeValues;
+use bevy::render::render_resource::{TextureViewDescriptor, TextureViewDimension};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BackgroundMode {
+    Solid(Color),
+    /// Vertical gradient from `top` to `bottom` across the view.
+    Gradient {
+        top: Color,
+        bottom: Color,
+    },
+    /// Cubemap image, either a cube texture (e.g. `.ktx2`) or six square
+    /// faces stacked vertically in a single image.
+    Skybox(String),
+}
+
+#[derive(Resource, Debug)]
+pub struct BackgroundConfig {
+    pub mode: BackgroundMode,
+}
+
+impl Default for BackgroundConfig {
+    fn default() -> Self {
+        Self {
+            mode: BackgroundMode::Solid(ClearColor::default().0),
+        }
+    }
+}
+
+/// Distance in front of the camera at which the gradient quad is drawn; just
+/// short of the default far plane.
+const GRADIENT_DISTANCE: f32 = 990.0;
+
+#[derive(Component)]
+struct GradientBackground;
+
+/// Skybox image still waiting to load, so it can be reinterpreted as a cube.
+#[derive(Resource)]
+struct PendingSkybox(Handle<Image>);
+
+pub struct BackgroundPlugin;
+
+impl Plugin for BackgroundPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BackgroundConfig>().add_systems(
+            Update,
+            (
+                apply_background,
+                fit_gradient_to_view,
+                prepare_skybox.run_if(resource_exists::<PendingSkybox>()),
+            )
+                .chain(),
+        );
+    }
+}
+
+fn gradient_mesh(top: Color, bottom: Color) -> Mesh {
+    let mut mesh = Mesh::from(shape::Quad::new(Vec2::ONE));
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        return mesh;
+    };
+    let colors: Vec<[f32; 4]> = positions
+        .iter()
+        .map(|p| {
+            let color = if p[1] > 0.0 { top } else { bottom };
+            color.as_linear_rgba_f32()
+        })
+        .collect();
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    mesh
+}
+
+/// Rebuilds the background when the config changes or a scene camera spawns.
+fn apply_background(
+    mut commands: Commands,
+    config: Res<BackgroundConfig>,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut clear_color: ResMut<ClearColor>,
+    cameras: Query<Entity, With<Camera3d>>,
+    new_cameras: Query<(), Added<Camera3d>>,
+    gradients: Query<Entity, With<GradientBackground>>,
+) {
+    if !config.is_changed() && new_cameras.is_empty() {
+        return;
+    }
+
+    for entity in &gradients {
+        commands.entity(entity).despawn_recursive();
+    }
+    for camera in &cameras {
+        commands.entity(camera).remove::<Skybox>();
+    }
+    commands.remove_resource::<PendingSkybox>();
+
+    match &config.mode {
+        BackgroundMode::Solid(color) => clear_color.0 = *color,
+        BackgroundMode::Gradient { top, bottom } => {
+            let mesh = meshes.add(gradient_mesh(*top, *bottom));
+            let material = materials.add(StandardMaterial {
+                unlit: true,
+                ..default()
+            });
+            for camera in &cameras {
+                let quad = commands
+                    .spawn((
+                        PbrBundle {
+                            mesh: mesh.clone(),
+                            material: material.clone(),
+                            transform: Transform::from_xyz(0.0, 0.0, -GRADIENT_DISTANCE),
+                            ..default()
+                        },
+                        NotShadowCaster,
+                        NotShadowReceiver,
+                        GradientBackground,
+                    ))
+                    .id();
+                commands.entity(camera).add_child(quad);
+            }
+        }
+        BackgroundMode::Skybox(path) => {
+            let image: Handle<Image> = asset_server.load(path);
+            for camera in &cameras {
+                commands.entity(camera).insert(Skybox(image.clone()));
+            }
+            commands.insert_resource(PendingSkybox(image));
+        }
+    }
+}
+
+/// Scales each gradient quad to exactly cover its camera's view.
+fn fit_gradient_to_view(
+    cameras: Query<&Projection>,
+    mut gradients: Query<(&Parent, &mut Transform), With<GradientBackground>>,
+) {
+    for (parent, mut transform) in &mut gradients {
+        let Ok(projection) = cameras.get(parent.get()) else {
+            continue;
+        };
+        let size = match projection {
+            Projection::Orthographic(ortho) => ortho.area.size(),
+            Projection::Perspective(perspective) => {
+                let height = 2.0 * GRADIENT_DISTANCE * (perspective.fov / 2.0).tan();
+                Vec2::new(height * perspective.aspect_ratio, height)
+            }
+        };
+        if size.min_element() > 0.0 {
+            transform.scale = size.extend(1.0);
+        }
+    }
+}
+
+/// Once the skybox image has loaded, turns a vertical strip of faces into the
+/// six-layer cube texture the skybox expects.
+fn prepare_skybox(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    pending: Res<PendingSkybox>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    match asset_server.get_load_state(pending.0.id()) {
+        Some(LoadState::Loaded) => {}
+        Some(LoadState::Failed) => {
+            println!("background: could not load the skybox image");
+            commands.remove_resource::<PendingSkybox>();
+            return;
+        }
+        _ => return,
+    }
+    commands.remove_resource::<PendingSkybox>();
+
+    let Some(image) = images.get_mut(&pending.0) else {
+        return;
+    };
+    if image.texture_descriptor.array_layer_count() == 1 {
+        let layers = image.height() / image.width().max(1);
+        if layers != 6 {
+            println!("background: skybox image should stack six square faces");
+            return;
+        }
+        image.reinterpret_stacked_2d_as_array(layers);
+        image.texture_view_descriptor = Some(TextureViewDescriptor {
+            dimension: Some(TextureViewDimension::Cube),
+            ..default()
+        });
+    }
+}
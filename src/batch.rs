@@ -0,0 +1,201 @@
+//! Headless validation of a whole directory of animation files.
+//!
+//! `animation_tools --validate <dir> [--report <file>]` loads every glTF in
+//! the directory, runs the clip diagnostics on each animation and writes the
+//! findings per file to the report, then exits.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::time::Duration;
+
+use bevy::app::{AppExit, ScheduleRunnerPlugin};
+use bevy::asset::RecursiveDependencyLoadState;
+use bevy::gltf::Gltf;
+use bevy::prelude::*;
+use bevy::render::settings::WgpuSettings;
+use bevy::render::RenderPlugin;
+use bevy::utils::HashSet;
+use bevy::winit::WinitPlugin;
+
+use crate::arg_value;
+use crate::diagnostics::{
+    clip_paths, duration_mismatch, find_rotation_flips, last_keyframe_time, path_string,
+    worst_loop_seam, ZERO_DURATION,
+};
+
+#[derive(Resource, Debug)]
+pub struct BatchValidation {
+    pub directory: String,
+    pub report_path: String,
+}
+
+impl BatchValidation {
+    /// Reads `--validate <dir>` and the optional `--report <file>` from the
+    /// command line.
+    pub fn from_args() -> Option<Self> {
+        Some(Self {
+            directory: arg_value("--validate")?,
+            report_path: arg_value("--report").unwrap_or_else(|| "validation_report.txt".into()),
+        })
+    }
+
+    pub fn run(self) {
+        App::new()
+            .add_plugins(
+                DefaultPlugins
+                    .set(AssetPlugin {
+                        file_path: self.directory.clone(),
+                        ..default()
+                    })
+                    .set(RenderPlugin {
+                        render_creation: WgpuSettings {
+                            backends: None,
+                            ..default()
+                        }
+                        .into(),
+                    })
+                    .disable::<WinitPlugin>(),
+            )
+            .add_plugins(ScheduleRunnerPlugin::run_loop(Duration::from_millis(10)))
+            .insert_resource(self)
+            .add_systems(Startup, load_directory)
+            .add_systems(Update, write_report)
+            .run();
+    }
+}
+
+#[derive(Resource)]
+struct PendingFiles(Vec<(String, Handle<Gltf>)>);
+
+fn load_directory(
+    mut commands: Commands,
+    config: Res<BatchValidation>,
+    asset_server: Res<AssetServer>,
+) {
+    let mut files: Vec<String> = fs::read_dir(&config.directory)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext == "glb" || ext == "gltf")
+        })
+        .filter_map(|path| Some(path.file_name()?.to_string_lossy().into_owned()))
+        .collect();
+    files.sort();
+    println!("validating {} file(s) in {}", files.len(), config.directory);
+
+    let pending = files
+        .into_iter()
+        .map(|file| {
+            let handle = asset_server.load(file.clone());
+            (file, handle)
+        })
+        .collect();
+    commands.insert_resource(PendingFiles(pending));
+}
+
+/// Issues found in one clip, one line each.
+fn clip_issues(clip: &AnimationClip, node_names: &HashSet<&str>) -> Vec<String> {
+    let mut issues = Vec::new();
+    if clip.duration() < ZERO_DURATION {
+        issues.push("zero duration".to_string());
+    }
+    if let Some(overshoot) = duration_mismatch(clip) {
+        issues.push(format!(
+            "last keyframe at {:.4}s but duration is {:.4}s ({overshoot:+.4}s)",
+            last_keyframe_time(clip),
+            clip.duration()
+        ));
+    }
+    if let Some(seam) = worst_loop_seam(clip) {
+        issues.push(format!(
+            "loop seam: {} jumps {:.1} degrees",
+            seam.bone, seam.degrees
+        ));
+    }
+    for flip in find_rotation_flips(clip) {
+        issues.push(format!(
+            "rotation sign flip: {} at {:.3}s (dot {:.2})",
+            flip.bone, flip.time, flip.dot
+        ));
+    }
+    let mut orphans: Vec<String> = clip_paths(clip)
+        .into_iter()
+        .filter(|(path, _)| {
+            path.parts
+                .iter()
+                .any(|name| !node_names.contains(name.as_str()))
+        })
+        .map(|(path, _)| path_string(path))
+        .collect();
+    orphans.sort();
+    issues.extend(
+        orphans
+            .into_iter()
+            .map(|path| format!("target not in file: {path}")),
+    );
+    issues
+}
+
+fn write_report(
+    config: Res<BatchValidation>,
+    pending: Option<Res<PendingFiles>>,
+    asset_server: Res<AssetServer>,
+    gltfs: Res<Assets<Gltf>>,
+    clips: Res<Assets<AnimationClip>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let Some(pending) = pending else {
+        return;
+    };
+    let still_loading = pending.0.iter().any(|(_, handle)| {
+        !matches!(
+            asset_server.get_recursive_dependency_load_state(handle.id()),
+            Some(RecursiveDependencyLoadState::Loaded | RecursiveDependencyLoadState::Failed)
+        )
+    });
+    if still_loading {
+        return;
+    }
+
+    let mut report = String::new();
+    let mut files_with_issues = 0;
+    for (file, handle) in &pending.0 {
+        let _ = writeln!(report, "{file}");
+        let Some(gltf) = gltfs.get(handle) else {
+            let _ = writeln!(report, "    could not be loaded");
+            files_with_issues += 1;
+            continue;
+        };
+
+        let node_names: HashSet<&str> = gltf.named_nodes.keys().map(String::as_str).collect();
+        let mut names: Vec<&String> = gltf.named_animations.keys().collect();
+        names.sort();
+        let mut issue_count = 0;
+        for name in names {
+            let Some(clip) = clips.get(&gltf.named_animations[name]) else {
+                continue;
+            };
+            for issue in clip_issues(clip, &node_names) {
+                let _ = writeln!(report, "    [{name}] {issue}");
+                issue_count += 1;
+            }
+        }
+        if issue_count == 0 {
+            let _ = writeln!(report, "    ok ({} clips)", gltf.named_animations.len());
+        } else {
+            files_with_issues += 1;
+        }
+    }
+
+    match fs::write(&config.report_path, report) {
+        Ok(()) => println!(
+            "{files_with_issues} of {} file(s) have issues, report written to {}",
+            pending.0.len(),
+            config.report_path
+        ),
+        Err(e) => println!("could not write {}: {e}", config.report_path),
+    }
+    exit.send(AppExit);
+}
@@ -0,0 +1,389 @@
+//! Imports BVH motion capture files as animation clips for the loaded skeleton.
+//!
+//! BVH joints are matched to skeleton bones by name. Joint rotations are
+//! applied relative to the bone's rest rotation, which is right when the BVH
+//! rest pose matches the glTF bind pose (e.g. mocap retargeted to the same
+//! rig); root position channels become translation curves.
+
+use std::fs;
+use std::path::Path;
+
+use bevy::animation::{Interpolation, Keyframes, VariableCurve};
+use bevy::prelude::*;
+
+use crate::skeleton::Skeleton;
+use crate::{AnimationParams, Animations, AnimationsMetadata};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Channel {
+    Xposition,
+    Yposition,
+    Zposition,
+    Xrotation,
+    Yrotation,
+    Zrotation,
+}
+
+impl Channel {
+    fn parse(name: &str) -> Result<Self, String> {
+        Ok(match name {
+            "Xposition" => Channel::Xposition,
+            "Yposition" => Channel::Yposition,
+            "Zposition" => Channel::Zposition,
+            "Xrotation" => Channel::Xrotation,
+            "Yrotation" => Channel::Yrotation,
+            "Zrotation" => Channel::Zrotation,
+            other => return Err(format!("unknown channel '{other}'")),
+        })
+    }
+}
+
+#[derive(Debug)]
+struct Joint {
+    name: String,
+    channels: Vec<Channel>,
+}
+
+#[derive(Debug)]
+pub struct Bvh {
+    joints: Vec<Joint>,
+    frame_time: f32,
+    /// One row of channel values per frame, in joint order.
+    frames: Vec<Vec<f32>>,
+}
+
+struct Tokens<'a>(std::str::SplitWhitespace<'a>);
+
+impl<'a> Tokens<'a> {
+    fn next(&mut self) -> Result<&'a str, String> {
+        self.0
+            .next()
+            .ok_or_else(|| "unexpected end of file".to_string())
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), String> {
+        match self.next()? {
+            token if token == expected => Ok(()),
+            token => Err(format!("expected '{expected}', found '{token}'")),
+        }
+    }
+
+    fn number<T: std::str::FromStr>(&mut self) -> Result<T, String> {
+        let token = self.next()?;
+        token
+            .parse()
+            .map_err(|_| format!("expected a number, found '{token}'"))
+    }
+}
+
+impl Bvh {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut tokens = Tokens(text.split_whitespace());
+        tokens.expect("HIERARCHY")?;
+
+        let mut joints: Vec<Joint> = Vec::new();
+        let mut open_joints: Vec<usize> = Vec::new();
+        loop {
+            match tokens.next()? {
+                "ROOT" | "JOINT" => {
+                    let name = tokens.next()?.to_string();
+                    tokens.expect("{")?;
+                    joints.push(Joint {
+                        name,
+                        channels: Vec::new(),
+                    });
+                    open_joints.push(joints.len() - 1);
+                }
+                "End" => {
+                    tokens.expect("Site")?;
+                    tokens.expect("{")?;
+                    tokens.expect("OFFSET")?;
+                    for _ in 0..3 {
+                        tokens.number::<f32>()?;
+                    }
+                    tokens.expect("}")?;
+                }
+                "OFFSET" => {
+                    for _ in 0..3 {
+                        tokens.number::<f32>()?;
+                    }
+                }
+                "CHANNELS" => {
+                    let count: usize = tokens.number()?;
+                    let joint = open_joints.last().ok_or("CHANNELS outside of a joint")?;
+                    for _ in 0..count {
+                        let channel = Channel::parse(tokens.next()?)?;
+                        joints[*joint].channels.push(channel);
+                    }
+                }
+                "}" => {
+                    open_joints.pop().ok_or("unbalanced '}'")?;
+                }
+                "MOTION" => break,
+                token => return Err(format!("unexpected '{token}' in hierarchy")),
+            }
+        }
+
+        tokens.expect("Frames:")?;
+        let frame_count: usize = tokens.number()?;
+        tokens.expect("Frame")?;
+        tokens.expect("Time:")?;
+        let frame_time: f32 = tokens.number()?;
+
+        let channels_per_frame = joints.iter().map(|joint| joint.channels.len()).sum();
+        let frames = (0..frame_count)
+            .map(|_| {
+                (0..channels_per_frame)
+                    .map(|_| tokens.number())
+                    .collect::<Result<Vec<f32>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            joints,
+            frame_time,
+            frames,
+        })
+    }
+
+    /// Builds a clip driving the matching bones of `skeleton`. Returns the
+    /// clip and the names of joints that had no matching bone.
+    pub fn to_clip(&self, skeleton: &Skeleton, scale: f32) -> (AnimationClip, Vec<String>) {
+        let mut clip = AnimationClip::default();
+        let mut unmatched = Vec::new();
+        let timestamps: Vec<f32> = (0..self.frames.len())
+            .map(|frame| frame as f32 * self.frame_time)
+            .collect();
+
+        let mut first_channel = 0;
+        for joint in &self.joints {
+            let channels = first_channel..first_channel + joint.channels.len();
+            first_channel = channels.end;
+
+            let Some(bone) = skeleton.find(&joint.name) else {
+                unmatched.push(joint.name.clone());
+                continue;
+            };
+
+            let mut rotations = Vec::with_capacity(self.frames.len());
+            let mut translations = Vec::with_capacity(self.frames.len());
+            for frame in &self.frames {
+                let mut rotation = Quat::IDENTITY;
+                let mut translation = Vec3::ZERO;
+                for (channel, &value) in joint.channels.iter().zip(&frame[channels.clone()]) {
+                    match channel {
+                        Channel::Xposition => translation.x = value * scale,
+                        Channel::Yposition => translation.y = value * scale,
+                        Channel::Zposition => translation.z = value * scale,
+                        Channel::Xrotation => rotation *= Quat::from_rotation_x(value.to_radians()),
+                        Channel::Yrotation => rotation *= Quat::from_rotation_y(value.to_radians()),
+                        Channel::Zrotation => rotation *= Quat::from_rotation_z(value.to_radians()),
+                    }
+                }
+                rotations.push(bone.rest.rotation * rotation);
+                translations.push(translation);
+            }
+
+            let has_position = joint.channels.iter().any(|channel| {
+                matches!(
+                    channel,
+                    Channel::Xposition | Channel::Yposition | Channel::Zposition
+                )
+            });
+            if has_position {
+                clip.add_curve_to_path(
+                    bone.path.clone(),
+                    VariableCurve {
+                        keyframe_timestamps: timestamps.clone(),
+                        keyframes: Keyframes::Translation(translations),
+                        interpolation: Interpolation::Linear,
+                    },
+                );
+            }
+            clip.add_curve_to_path(
+                bone.path.clone(),
+                VariableCurve {
+                    keyframe_timestamps: timestamps.clone(),
+                    keyframes: Keyframes::Rotation(rotations),
+                    interpolation: Interpolation::Linear,
+                },
+            );
+        }
+        (clip, unmatched)
+    }
+}
+
+#[derive(Resource, Debug)]
+pub struct BvhImportConfig {
+    pub files: Vec<String>,
+    /// Multiplier from BVH position units to the skeleton's local units.
+    pub scale: f32,
+}
+
+impl Default for BvhImportConfig {
+    /// Every `.bvh` file directly inside `assets/`.
+    fn default() -> Self {
+        let files = fs::read_dir("assets")
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "bvh"))
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect();
+        Self { files, scale: 1.0 }
+    }
+}
+
+pub struct BvhImportPlugin;
+
+impl Plugin for BvhImportPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BvhImportConfig>().add_systems(
+            Update,
+            import_bvh_files.run_if(resource_exists::<Animations>()),
+        );
+    }
+}
+
+/// Imports the configured files once the skeleton they are mapped onto exists.
+fn import_bvh_files(
+    config: Res<BvhImportConfig>,
+    skeleton: Res<Skeleton>,
+    mut clips: ResMut<Assets<AnimationClip>>,
+    mut animations: ResMut<Animations>,
+    mut animation_meta: ResMut<AnimationsMetadata>,
+    mut imported: Local<bool>,
+) {
+    if *imported || skeleton.root.is_none() {
+        return;
+    }
+    *imported = true;
+
+    for file in &config.files {
+        let bvh = fs::read_to_string(file)
+            .map_err(|e| e.to_string())
+            .and_then(|text| Bvh::parse(&text));
+        let bvh = match bvh {
+            Ok(bvh) => bvh,
+            Err(e) => {
+                println!("bvh: could not import {file}: {e}");
+                continue;
+            }
+        };
+
+        let (clip, unmatched) = bvh.to_clip(&skeleton, config.scale);
+        if !unmatched.is_empty() {
+            println!("bvh: {file}: no bone for joint(s) {}", unmatched.join(", "));
+        }
+        let name = Path::new(file)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| file.clone());
+        println!("bvh: imported {name} ({} frames)", bvh.frames.len());
+
+        animations.0.push(clips.add(clip));
+        animation_meta.0.push(AnimationParams::new(file, &name));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TWO_JOINTS: &str = "HIERARCHY
+ROOT Hips
+{
+    OFFSET 0 0 0
+    CHANNELS 6 Xposition Yposition Zposition Zrotation Xrotation Yrotation
+    JOINT Spine
+    {
+        OFFSET 0 10 0
+        CHANNELS 3 Zrotation Xrotation Yrotation
+        End Site
+        {
+            OFFSET 0 5 0
+        }
+    }
+}
+MOTION
+Frames: 2
+Frame Time: 0.033333
+0 90 0 0 0 0 10 20 30
+1 91 2 0 45 0 11 21 31
+";
+
+    #[test]
+    fn parses_hierarchy_and_channels() {
+        let bvh = Bvh::parse(TWO_JOINTS).unwrap();
+        let names: Vec<_> = bvh.joints.iter().map(|joint| joint.name.as_str()).collect();
+        assert_eq!(names, ["Hips", "Spine"]);
+        assert_eq!(
+            bvh.joints[0].channels,
+            [
+                Channel::Xposition,
+                Channel::Yposition,
+                Channel::Zposition,
+                Channel::Zrotation,
+                Channel::Xrotation,
+                Channel::Yrotation,
+            ]
+        );
+        assert_eq!(
+            bvh.joints[1].channels,
+            [Channel::Zrotation, Channel::Xrotation, Channel::Yrotation]
+        );
+    }
+
+    #[test]
+    fn parses_motion() {
+        let bvh = Bvh::parse(TWO_JOINTS).unwrap();
+        assert_eq!(bvh.frame_time, 0.033333);
+        assert_eq!(bvh.frames.len(), 2);
+        assert_eq!(
+            bvh.frames[1],
+            [1.0, 91.0, 2.0, 0.0, 45.0, 0.0, 11.0, 21.0, 31.0]
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_channel() {
+        let text = TWO_JOINTS.replace(
+            "Zrotation Xrotation Yrotation\n        End",
+            "Wrotation Xrotation Yrotation\n        End",
+        );
+        assert_eq!(
+            Bvh::parse(&text).unwrap_err(),
+            "unknown channel 'Wrotation'"
+        );
+    }
+
+    #[test]
+    fn rejects_missing_header() {
+        let text = TWO_JOINTS.replacen("HIERARCHY", "", 1);
+        assert_eq!(
+            Bvh::parse(&text).unwrap_err(),
+            "expected 'HIERARCHY', found 'ROOT'"
+        );
+    }
+
+    #[test]
+    fn rejects_unbalanced_braces() {
+        let text = TWO_JOINTS.replace("MOTION", "}\nMOTION");
+        assert_eq!(Bvh::parse(&text).unwrap_err(), "unbalanced '}'");
+    }
+
+    #[test]
+    fn rejects_truncated_motion() {
+        let text = TWO_JOINTS.trim_end().trim_end_matches("11 21 31");
+        assert_eq!(Bvh::parse(text).unwrap_err(), "unexpected end of file");
+    }
+
+    #[test]
+    fn rejects_non_numeric_values() {
+        let text = TWO_JOINTS.replace("11 21 31", "11 abc 31");
+        assert_eq!(
+            Bvh::parse(&text).unwrap_err(),
+            "expected a number, found 'abc'"
+        );
+    }
+}
@@ -0,0 +1,591 @@
+//! Camera helpers for inspecting the character up close.
+
+use std::fs;
+use std::io;
+
+use bevy::input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel};
+use bevy::prelude::*;
+use bevy::render::camera::RenderTarget;
+use bevy::render::primitives::Aabb;
+use bevy::render::render_resource::{
+    Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+};
+use bevy::window::PrimaryWindow;
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+use bevy_inspector_egui::egui;
+use serde::{Deserialize, Serialize};
+
+use crate::ui::keyboard_free;
+use crate::{arg_value, ModelRoot};
+
+pub const CAMERA_PRESETS_PATH: &str = "assets/camera_presets.ron";
+
+/// Keeps the camera's near plane outside the character's bounds by easing it
+/// backwards along its view direction when anything would be clipped.
+#[derive(Resource, Debug)]
+pub struct CameraAutoDistance {
+    pub enabled: bool,
+    /// Clearance kept between the near plane and the closest point of the bounds.
+    pub margin: f32,
+    /// Higher values settle faster.
+    pub smoothing: f32,
+}
+
+impl Default for CameraAutoDistance {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            margin: 0.5,
+            smoothing: 8.0,
+        }
+    }
+}
+
+/// Renders the scene into an off-screen image of a fixed size, shown scaled to
+/// fit the window, so the rendered frame doesn't depend on the window size.
+/// Set in the "Render resolution" panel or with `--render-size 1920x1080`.
+///
+/// Window screenshots still capture the window itself, not the off-screen
+/// image.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct RenderResolution {
+    pub enabled: bool,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for RenderResolution {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            width: 1920,
+            height: 1080,
+        }
+    }
+}
+
+impl RenderResolution {
+    /// Enabled at the size given as `--render-size <width>x<height>`, or the
+    /// default if there's none.
+    pub fn from_args() -> Self {
+        let Some(size) = arg_value("--render-size") else {
+            return Self::default();
+        };
+        let parsed = size
+            .split_once('x')
+            .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)))
+            .filter(|&(width, height)| width > 0 && height > 0);
+        match parsed {
+            Some((width, height)) => Self {
+                enabled: true,
+                width,
+                height,
+            },
+            None => {
+                println!("error: --render-size {size}: expected <width>x<height>, e.g. 1920x1080");
+                Self::default()
+            }
+        }
+    }
+}
+
+/// The second camera and the UI node showing the fixed-resolution image,
+/// replaced whenever the resolution changes.
+#[derive(Component)]
+struct FixedResolutionView;
+
+/// Mouse camera controls: left-drag orbits around `target`, middle-drag pans
+/// the target and the wheel zooms.
+#[derive(Resource, Debug)]
+pub struct OrbitCamera {
+    pub target: Vec3,
+    /// Radians turned per pixel dragged.
+    pub rotate_speed: f32,
+    /// Fraction of the zoom changed per wheel notch.
+    pub zoom_speed: f32,
+    /// Limits of the orthographic projection's scale.
+    pub min_scale: f32,
+    pub max_scale: f32,
+}
+
+impl Default for OrbitCamera {
+    fn default() -> Self {
+        Self {
+            target: Vec3::ZERO,
+            rotate_speed: 0.005,
+            zoom_speed: 0.1,
+            min_scale: 0.25,
+            max_scale: 20.0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CameraPreset {
+    pub name: String,
+    pub transform: Transform,
+}
+
+/// Named camera placements shared through `assets/camera_presets.ron`.
+#[derive(Resource, Serialize, Deserialize, Debug, Default)]
+pub struct CameraPresets {
+    pub presets: Vec<CameraPreset>,
+    /// Index of the preset last applied, for cycling.
+    #[serde(skip)]
+    pub current: Option<usize>,
+    /// File new presets are saved to. `None` when the presets came from
+    /// somewhere they shouldn't be written over: a project file, or a
+    /// presets file that failed to parse. New presets then last the session.
+    #[serde(skip)]
+    pub file: Option<String>,
+}
+
+impl CameraPresets {
+    pub fn from_file(path: &str) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        ron::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(path, text)
+    }
+}
+
+pub struct CameraToolsPlugin;
+
+impl Plugin for CameraToolsPlugin {
+    fn build(&self, app: &mut App) {
+        let presets = match CameraPresets::from_file(CAMERA_PRESETS_PATH) {
+            Ok(presets) => CameraPresets {
+                file: Some(CAMERA_PRESETS_PATH.to_string()),
+                ..presets
+            },
+            Err(e) if e.kind() == io::ErrorKind::NotFound => CameraPresets {
+                file: Some(CAMERA_PRESETS_PATH.to_string()),
+                ..default()
+            },
+            Err(e) => {
+                println!("camera presets: could not load {CAMERA_PRESETS_PATH}: {e}");
+                CameraPresets::default()
+            }
+        };
+        app.init_resource::<CameraAutoDistance>()
+            .insert_resource(RenderResolution::from_args())
+            .init_resource::<OrbitCamera>()
+            .insert_resource(presets)
+            .add_systems(
+                Update,
+                (
+                    (
+                        toggle_auto_distance.run_if(keyboard_free),
+                        dolly_out_of_bounds
+                            .run_if(|config: Res<CameraAutoDistance>| config.enabled),
+                    )
+                        .chain(),
+                    (render_resolution_panel, render_at_fixed_resolution).chain(),
+                    (
+                        keyboard_camera_presets.run_if(keyboard_free),
+                        camera_presets_panel,
+                    ),
+                    (snap_to_view.run_if(keyboard_free), orbit_camera).chain(),
+                ),
+            );
+    }
+}
+
+/// World-space corners of every mesh's bounding box.
+pub fn mesh_bound_corners(
+    meshes: &Query<(&Aabb, &GlobalTransform), With<Handle<Mesh>>>,
+) -> Vec<Vec3> {
+    meshes
+        .iter()
+        .flat_map(|(aabb, global)| {
+            let center = Vec3::from(aabb.center);
+            let half = Vec3::from(aabb.half_extents);
+            (0..8).map(move |i| {
+                let sign = Vec3::new(
+                    if i & 1 == 0 { -1.0 } else { 1.0 },
+                    if i & 2 == 0 { -1.0 } else { 1.0 },
+                    if i & 4 == 0 { -1.0 } else { 1.0 },
+                );
+                global.transform_point(center + half * sign)
+            })
+        })
+        .collect()
+}
+
+fn toggle_auto_distance(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut config: ResMut<CameraAutoDistance>,
+) {
+    if keyboard_input.just_pressed(KeyCode::D) {
+        config.enabled = !config.enabled;
+        println!("camera auto-distance: {}", config.enabled);
+    }
+}
+
+fn dolly_out_of_bounds(
+    config: Res<CameraAutoDistance>,
+    time: Res<Time>,
+    meshes: Query<(&Aabb, &GlobalTransform), With<Handle<Mesh>>>,
+    mut cameras: Query<(&mut Transform, &Projection), With<Camera3d>>,
+) {
+    let corners = mesh_bound_corners(&meshes);
+    if corners.is_empty() {
+        return;
+    }
+    for (mut transform, projection) in &mut cameras {
+        let near = match projection {
+            Projection::Orthographic(ortho) => ortho.near,
+            Projection::Perspective(perspective) => perspective.near,
+        };
+        let forward = transform.forward();
+        let closest = corners
+            .iter()
+            .map(|&corner| (corner - transform.translation).dot(forward))
+            .fold(f32::INFINITY, f32::min);
+        let shortfall = near + config.margin - closest;
+        if shortfall > 0.0 {
+            let blend = 1.0 - (-config.smoothing * time.delta_seconds()).exp();
+            transform.translation -= forward * shortfall * blend;
+        }
+    }
+}
+
+fn orbit_camera(
+    mut contexts: EguiContexts,
+    mut orbit: ResMut<OrbitCamera>,
+    mouse_input: Res<Input<MouseButton>>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut motion: EventReader<MouseMotion>,
+    mut wheel: EventReader<MouseWheel>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut cameras: Query<(&mut Transform, &mut Projection), With<Camera3d>>,
+) {
+    let drag: Vec2 = motion.read().map(|motion| motion.delta).sum();
+    let scroll: f32 = wheel
+        .read()
+        .map(|wheel| match wheel.unit {
+            MouseScrollUnit::Line => wheel.y,
+            MouseScrollUnit::Pixel => wheel.y / 50.0,
+        })
+        .sum();
+    // The motion path editor has the mouse while grave is held.
+    if contexts.ctx_mut().wants_pointer_input() || keyboard_input.pressed(KeyCode::Grave) {
+        return;
+    }
+    let window_height = windows
+        .iter()
+        .next()
+        .map_or(720.0, |window| window.height());
+
+    for (mut transform, mut projection) in &mut cameras {
+        let offset = transform.translation - orbit.target;
+        if mouse_input.pressed(MouseButton::Left) && drag != Vec2::ZERO {
+            let yaw = Quat::from_rotation_y(-drag.x * orbit.rotate_speed);
+            let pitch = Quat::from_axis_angle(transform.right(), -drag.y * orbit.rotate_speed);
+            // Stop pitching short of straight up or down, where the view
+            // would flip over.
+            let pitched = pitch * offset;
+            let offset = if pitched.normalize_or_zero().y.abs() < 0.99 {
+                yaw * pitched
+            } else {
+                yaw * offset
+            };
+            transform.translation = orbit.target + offset;
+            transform.look_at(orbit.target, Vec3::Y);
+        }
+
+        if mouse_input.pressed(MouseButton::Middle) && drag != Vec2::ZERO {
+            let units_per_pixel = match &*projection {
+                Projection::Orthographic(ortho) => ortho.area.height() / window_height,
+                Projection::Perspective(perspective) => {
+                    2.0 * offset.length() * (perspective.fov / 2.0).tan() / window_height
+                }
+            };
+            let pan = (transform.up() * drag.y - transform.right() * drag.x) * units_per_pixel;
+            orbit.target += pan;
+            transform.translation += pan;
+        }
+
+        if scroll != 0.0 {
+            let factor = (1.0 - scroll * orbit.zoom_speed).clamp(0.5, 1.5);
+            match &mut *projection {
+                Projection::Orthographic(ortho) => {
+                    ortho.scale = (ortho.scale * factor).clamp(orbit.min_scale, orbit.max_scale);
+                }
+                Projection::Perspective(_) => {
+                    let distance = (offset.length() * factor).max(orbit.min_scale);
+                    transform.translation = orbit.target + offset.normalize_or_zero() * distance;
+                }
+            }
+        }
+    }
+}
+
+fn render_resolution_panel(mut contexts: EguiContexts, mut resolution: ResMut<RenderResolution>) {
+    let mut config = resolution.clone();
+    egui::Window::new("Render resolution")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.checkbox(&mut config.enabled, "render at a fixed size");
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut config.width).clamp_range(16..=8192));
+                ui.label("x");
+                ui.add(egui::DragValue::new(&mut config.height).clamp_range(16..=8192));
+            });
+        });
+    if config != *resolution {
+        *resolution = config;
+    }
+}
+
+/// Retargets the scene camera to a fixed-size image and displays that image in
+/// the window through a UI node, or back to the window when switched off.
+/// Redone when the settings change or a scene camera is spawned.
+fn render_at_fixed_resolution(
+    mut commands: Commands,
+    config: Res<RenderResolution>,
+    mut images: ResMut<Assets<Image>>,
+    mut cameras: Query<(Entity, &mut Camera), With<Camera3d>>,
+    added: Query<(), Added<Camera3d>>,
+    views: Query<Entity, With<FixedResolutionView>>,
+) {
+    if !config.is_changed() && added.is_empty() {
+        return;
+    }
+    let was_fixed = !views.is_empty();
+    for view in &views {
+        commands.entity(view).despawn_recursive();
+    }
+    if !config.enabled {
+        for (entity, mut camera) in &mut cameras {
+            camera.target = RenderTarget::default();
+            commands.entity(entity).remove::<UiCameraConfig>();
+        }
+        if was_fixed {
+            println!("rendering the scene at the window size");
+        }
+        return;
+    }
+
+    for (entity, mut camera) in &mut cameras {
+        let size = Extent3d {
+            width: config.width,
+            height: config.height,
+            ..default()
+        };
+        let mut image = Image {
+            texture_descriptor: TextureDescriptor {
+                label: Some("fixed_resolution_target"),
+                size,
+                dimension: TextureDimension::D2,
+                format: TextureFormat::Bgra8UnormSrgb,
+                mip_level_count: 1,
+                sample_count: 1,
+                usage: TextureUsages::TEXTURE_BINDING
+                    | TextureUsages::COPY_DST
+                    | TextureUsages::RENDER_ATTACHMENT,
+                view_formats: &[],
+            },
+            ..default()
+        };
+        image.resize(size);
+        let image = images.add(image);
+
+        camera.target = RenderTarget::Image(image.clone());
+        // The UI belongs on the window, not baked into the scene image.
+        commands
+            .entity(entity)
+            .insert(UiCameraConfig { show_ui: false });
+
+        commands.spawn((
+            Camera2dBundle {
+                camera: Camera {
+                    order: 1,
+                    ..default()
+                },
+                ..default()
+            },
+            FixedResolutionView,
+        ));
+        commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.0),
+                        height: Val::Percent(100.0),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    z_index: ZIndex::Global(-1),
+                    ..default()
+                },
+                FixedResolutionView,
+            ))
+            .with_children(|parent| {
+                parent.spawn(ImageBundle {
+                    style: Style {
+                        max_width: Val::Percent(100.0),
+                        max_height: Val::Percent(100.0),
+                        aspect_ratio: Some(config.width as f32 / config.height as f32),
+                        ..default()
+                    },
+                    image: UiImage::new(image),
+                    ..default()
+                });
+            });
+        println!(
+            "rendering the scene at a fixed {}x{}",
+            config.width, config.height
+        );
+    }
+}
+
+fn apply_preset(
+    presets: &mut CameraPresets,
+    index: usize,
+    cameras: &mut Query<&mut Transform, With<Camera3d>>,
+) {
+    let Some(preset) = presets.presets.get(index) else {
+        return;
+    };
+    for mut transform in cameras.iter_mut() {
+        *transform = preset.transform;
+    }
+    println!("camera preset: {}", preset.name);
+    presets.current = Some(index);
+}
+
+fn save_preset(presets: &mut CameraPresets, name: String, transform: Transform) {
+    presets.presets.push(CameraPreset {
+        name: name.clone(),
+        transform,
+    });
+    presets.current = Some(presets.presets.len() - 1);
+    let Some(path) = presets.file.clone() else {
+        println!("camera preset '{name}' added for this session only");
+        return;
+    };
+    match presets.save(&path) {
+        Ok(()) => println!("camera preset '{name}' saved to {path}"),
+        Err(e) => println!("could not write {path}: {e}"),
+    }
+}
+
+/// Canonical views by numpad key, as directions from the character to the
+/// camera in the character's own space, and the camera's up direction.
+const VIEWS: [(KeyCode, &str, Vec3, Vec3); 4] = [
+    (KeyCode::Numpad1, "front", Vec3::Z, Vec3::Y),
+    (KeyCode::Numpad3, "side", Vec3::X, Vec3::Y),
+    (KeyCode::Numpad7, "top", Vec3::Y, Vec3::NEG_Z),
+    (
+        KeyCode::Numpad5,
+        "three-quarter",
+        Vec3::new(1.0, 0.6, 1.0),
+        Vec3::Y,
+    ),
+];
+
+/// Snaps the camera to a front, side, top or three-quarter view of the
+/// character, keeping its distance and zoom, and re-centers the orbit on the
+/// character.
+fn snap_to_view(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut orbit: ResMut<OrbitCamera>,
+    bounds: Query<(&Aabb, &GlobalTransform), With<Handle<Mesh>>>,
+    roots: Query<&GlobalTransform, With<ModelRoot>>,
+    mut cameras: Query<&mut Transform, With<Camera3d>>,
+) {
+    let Some(&(_, name, direction, up)) = VIEWS
+        .iter()
+        .find(|(key, ..)| keyboard_input.just_pressed(*key))
+    else {
+        return;
+    };
+    let corners = mesh_bound_corners(&bounds);
+    if !corners.is_empty() {
+        let (min, max) = corners.iter().fold(
+            (Vec3::splat(f32::INFINITY), Vec3::splat(f32::NEG_INFINITY)),
+            |(min, max), &corner| (min.min(corner), max.max(corner)),
+        );
+        orbit.target = (min + max) / 2.0;
+    }
+    let rotation = roots
+        .iter()
+        .next()
+        .map_or(Quat::IDENTITY, |root| root.compute_transform().rotation);
+    for mut transform in &mut cameras {
+        let distance = transform.translation.distance(orbit.target).max(1.0);
+        *transform =
+            Transform::from_translation(orbit.target + rotation * direction.normalize() * distance)
+                .looking_at(orbit.target, rotation * up);
+    }
+    println!("camera view: {name}");
+}
+
+/// `N` cycles through the presets; shift+`N` saves the current camera as a
+/// new one.
+fn keyboard_camera_presets(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut presets: ResMut<CameraPresets>,
+    mut cameras: Query<&mut Transform, With<Camera3d>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::N) {
+        return;
+    }
+    if keyboard_input.pressed(KeyCode::ShiftLeft) {
+        let Some(transform) = cameras.iter().next().copied() else {
+            return;
+        };
+        let name = format!("view {}", presets.presets.len() + 1);
+        save_preset(&mut presets, name, transform);
+    } else if !presets.presets.is_empty() {
+        let next = presets
+            .current
+            .map_or(0, |current| (current + 1) % presets.presets.len());
+        apply_preset(&mut presets, next, &mut cameras);
+    }
+}
+
+fn camera_presets_panel(
+    mut contexts: EguiContexts,
+    mut presets: ResMut<CameraPresets>,
+    mut cameras: Query<&mut Transform, With<Camera3d>>,
+    mut new_name: Local<String>,
+) {
+    egui::Window::new("Camera presets")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            let mut selected = None;
+            for (index, preset) in presets.presets.iter().enumerate() {
+                if ui
+                    .selectable_label(presets.current == Some(index), preset.name.as_str())
+                    .clicked()
+                {
+                    selected = Some(index);
+                }
+            }
+            if let Some(index) = selected {
+                apply_preset(&mut presets, index, &mut cameras);
+            }
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut *new_name);
+                let save = ui
+                    .add_enabled(
+                        !new_name.trim().is_empty(),
+                        egui::Button::new("save current"),
+                    )
+                    .clicked();
+                if let (true, Some(transform)) = (save, cameras.iter().next().copied()) {
+                    save_preset(&mut presets, new_name.trim().to_string(), transform);
+                    new_name.clear();
+                }
+            });
+        });
+}
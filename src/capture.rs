@@ -0,0 +1,953 @@
+//! Offline capture of the playing clip into still images.
+
+use std::fs;
+use std::sync::{Arc, Mutex};
+
+use bevy::app::AppExit;
+use bevy::asset::LoadState;
+use bevy::core_pipeline::tonemapping::Tonemapping;
+use bevy::prelude::*;
+use bevy::render::view::screenshot::ScreenshotManager;
+use bevy::render::view::ColorGrading;
+use bevy::window::PrimaryWindow;
+use bevy_inspector_egui::bevy_egui::{EguiContexts, EguiRenderOutput, EguiSet};
+use bevy_inspector_egui::egui;
+use image::imageops::FilterType;
+use serde::Serialize;
+
+use crate::diagnostics::clip_name;
+use crate::ui::keyboard_free;
+use crate::{arg_value, Animations, AnimationsMetadata};
+
+/// Layout and destination of the contact sheet produced by `F9`, editable in
+/// the "Capture" panel.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct ContactSheetConfig {
+    pub columns: u32,
+    pub rows: u32,
+    pub output_path: String,
+}
+
+impl Default for ContactSheetConfig {
+    fn default() -> Self {
+        Self {
+            columns: 4,
+            rows: 2,
+            output_path: "contact_sheet.png".to_string(),
+        }
+    }
+}
+
+/// Layout and destination of the sprite sheet produced by `F11`: one loop of
+/// the current clip rendered into a packed grid of equally sized frames, for
+/// 2D games that use pre-rendered characters. Everything but the camera is
+/// editable in the "Capture" panel.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct SpriteSheetConfig {
+    pub frames: u32,
+    pub columns: u32,
+    /// Size of each frame in the sheet. Captures are center-cropped to this
+    /// aspect ratio, then scaled.
+    pub frame_width: u32,
+    pub frame_height: u32,
+    /// Camera placement while rendering; `None` keeps the current view.
+    pub camera: Option<Transform>,
+    pub output_path: String,
+    /// Frame rectangles and timing, written as RON next to the sheet.
+    pub metadata_path: String,
+}
+
+impl Default for SpriteSheetConfig {
+    fn default() -> Self {
+        Self {
+            frames: 12,
+            columns: 6,
+            frame_width: 256,
+            frame_height: 256,
+            camera: None,
+            output_path: "sprite_sheet.png".to_string(),
+            metadata_path: "sprite_sheet.ron".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct SpriteFrame {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Clip time the frame was rendered at, in seconds.
+    pub time: f32,
+}
+
+#[derive(Serialize, Debug)]
+pub struct SpriteSheetMetadata {
+    pub clip: String,
+    /// Seconds each frame is shown for to play back at the clip's speed.
+    pub frame_duration: f32,
+    pub frames: Vec<SpriteFrame>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// Label rendered into the frame while a capture is running, so exported
+/// stills say what they are. `template` may contain `{project}`, `{clip}`,
+/// `{frame}` and `{time}`.
+#[derive(Resource, Debug)]
+pub struct WatermarkConfig {
+    pub enabled: bool,
+    pub template: String,
+    pub project: String,
+    pub corner: Corner,
+    /// Frame rate used to turn the sample time into a frame number.
+    pub fps: f32,
+}
+
+impl Default for WatermarkConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            template: "{project}  {clip}  frame {frame} ({time}s)".to_string(),
+            project: "animation_tools".to_string(),
+            corner: Corner::BottomLeft,
+            fps: 30.0,
+        }
+    }
+}
+
+impl WatermarkConfig {
+    pub fn format(&self, clip: &str, time: f32) -> String {
+        self.template
+            .replace("{project}", &self.project)
+            .replace("{clip}", clip)
+            .replace("{frame}", &((time * self.fps).round() as i32).to_string())
+            .replace("{time}", &format!("{time:.3}"))
+    }
+}
+
+#[derive(Component)]
+struct Watermark;
+
+/// Fixed tonemapping and color grading forced onto the camera for the
+/// duration of a capture, so every frame of every capture is graded alike no
+/// matter how the camera was tweaked interactively.
+#[derive(Resource, Debug)]
+pub struct ExposureLock {
+    pub enabled: bool,
+    pub tonemapping: Tonemapping,
+    pub color_grading: ColorGrading,
+}
+
+impl Default for ExposureLock {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            tonemapping: Tonemapping::TonyMcMapface,
+            color_grading: ColorGrading::default(),
+        }
+    }
+}
+
+/// `--export <dir> [--export-frames <n>]`: screenshots every clip at `n`
+/// evenly spaced times into `<dir>/<name>_<frame>.png`, one at a time, then
+/// exits. A clip that fails to load, or hasn't loaded after
+/// `CLIP_LOAD_TIMEOUT` seconds, is skipped.
+#[derive(Resource, Debug)]
+pub struct BatchExport {
+    pub directory: String,
+    pub frames: u32,
+    clip: usize,
+    frame: u32,
+    /// Frames left before the screenshot is requested, once the clip has
+    /// been seeked.
+    settle: Option<u32>,
+    requested: u32,
+    /// Screenshots written so far, counted from the screenshot callbacks.
+    written: Arc<Mutex<u32>>,
+    /// Seconds spent waiting for the current clip to load.
+    load_wait: f32,
+}
+
+impl BatchExport {
+    pub fn from_args() -> Option<Self> {
+        Some(Self {
+            directory: arg_value("--export")?,
+            frames: arg_value("--export-frames")
+                .and_then(|frames| frames.parse().ok())
+                .unwrap_or(8),
+            clip: 0,
+            frame: 0,
+            settle: None,
+            requested: 0,
+            written: Arc::default(),
+            load_wait: 0.0,
+        })
+    }
+}
+
+/// Frame rate and destination of the PNG sequence produced by `F12`, one
+/// loop of the current clip as `<directory>/frame_0000.png` onwards, e.g. to
+/// turn into a GIF. `--frames-dir <dir>` and `--frames-fps <fps>` override
+/// the defaults.
+#[derive(Resource, Debug)]
+pub struct FrameSequenceConfig {
+    pub fps: f32,
+    pub directory: String,
+}
+
+impl Default for FrameSequenceConfig {
+    fn default() -> Self {
+        Self {
+            fps: 30.0,
+            directory: "frames".to_string(),
+        }
+    }
+}
+
+impl FrameSequenceConfig {
+    pub fn from_args() -> Self {
+        let default = Self::default();
+        Self {
+            fps: arg_value("--frames-fps")
+                .and_then(|fps| fps.parse().ok())
+                .filter(|&fps: &f32| fps > 0.0)
+                .unwrap_or(default.fps),
+            directory: arg_value("--frames-dir").unwrap_or(default.directory),
+        }
+    }
+}
+
+/// In-flight PNG sequence capture. Like the batch export, each frame is
+/// seeked, left to settle and screenshotted, and the next frame waits until
+/// the previous screenshot has been written.
+#[derive(Resource, Debug)]
+pub struct FrameSequence {
+    sample_times: Vec<f32>,
+    frame: usize,
+    settle: Option<u32>,
+    requested: u32,
+    written: Arc<Mutex<u32>>,
+    was_paused: bool,
+    resume_at: f32,
+}
+
+/// Number of frames to wait after seeking so the new pose is rendered before
+/// the screenshot is requested.
+const SETTLE_FRAMES: u32 = 2;
+
+/// Seconds the batch export waits for a clip to load before skipping it.
+const CLIP_LOAD_TIMEOUT: f32 = 10.0;
+
+#[derive(Debug, Clone, PartialEq)]
+enum SheetKind {
+    Contact,
+    Sprite { clip: String },
+}
+
+/// In-flight contact or sprite sheet capture. Frames are captured one at a
+/// time: seek, let the pose settle, request a screenshot, wait for it to land.
+#[derive(Resource)]
+pub struct SheetCapture {
+    kind: SheetKind,
+    sample_times: Vec<f32>,
+    next_sample: usize,
+    settle: u32,
+    awaiting_screenshot: bool,
+    images: Arc<Mutex<Vec<Image>>>,
+    was_paused: bool,
+    resume_at: f32,
+    /// Camera transforms to restore if the capture moved the camera.
+    saved_cameras: Vec<(Entity, Transform)>,
+}
+
+pub struct ContactSheetPlugin;
+
+impl Plugin for ContactSheetPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ContactSheetConfig>()
+            .init_resource::<SpriteSheetConfig>()
+            .init_resource::<WatermarkConfig>()
+            .init_resource::<ExposureLock>()
+            .insert_resource(FrameSequenceConfig::from_args())
+            .add_systems(Startup, spawn_watermark)
+            .add_systems(
+                Update,
+                (
+                    (
+                        start_contact_sheet,
+                        start_sprite_sheet,
+                        start_frame_sequence,
+                    )
+                        .run_if(not(resource_exists::<SheetCapture>()))
+                        .run_if(not(resource_exists::<FrameSequence>()))
+                        .run_if(keyboard_free),
+                    step_contact_sheet.run_if(resource_exists::<SheetCapture>()),
+                    step_frame_sequence.run_if(resource_exists::<FrameSequence>()),
+                    capture_panel,
+                    update_watermark,
+                    capturing.pipe(lock_exposure_during_capture),
+                    step_batch_export
+                        .run_if(resource_exists::<BatchExport>())
+                        .run_if(resource_exists::<Animations>()),
+                ),
+            )
+            .add_systems(
+                PostUpdate,
+                hide_panels_during_capture
+                    .run_if(capturing)
+                    .after(EguiSet::ProcessOutput),
+            );
+    }
+}
+
+/// Run condition: true while a capture is writing frames, which keeps the HUD
+/// and the egui panels out of them.
+pub fn capturing(
+    sheet: Option<Res<SheetCapture>>,
+    sequence: Option<Res<FrameSequence>>,
+    export: Option<Res<BatchExport>>,
+) -> bool {
+    sheet.is_some() || sequence.is_some() || export.is_some()
+}
+
+/// Drops egui's output for the frame so panels don't end up in captures.
+/// Their state is untouched and they're drawn again once capture ends.
+fn hide_panels_during_capture(mut outputs: Query<&mut EguiRenderOutput>) {
+    for mut output in &mut outputs {
+        output.paint_jobs.clear();
+    }
+}
+
+/// Evenly spaced sample times across `[0, duration)`.
+pub fn sample_times(duration: f32, count: u32) -> Vec<f32> {
+    (0..count)
+        .map(|i| duration * i as f32 / count as f32)
+        .collect()
+}
+
+fn capture_panel(
+    mut contexts: EguiContexts,
+    mut contact_sheet: ResMut<ContactSheetConfig>,
+    mut sprite_sheet: ResMut<SpriteSheetConfig>,
+) {
+    let mut config = contact_sheet.clone();
+    let mut sprite_config = sprite_sheet.clone();
+    egui::Window::new("Capture")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label("contact sheet (F9)");
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut config.columns).clamp_range(1..=16));
+                ui.label("columns");
+                ui.add(egui::DragValue::new(&mut config.rows).clamp_range(1..=16));
+                ui.label("rows");
+            });
+            ui.horizontal(|ui| {
+                ui.label("file");
+                ui.text_edit_singleline(&mut config.output_path);
+            });
+
+            ui.separator();
+            ui.label("sprite sheet (F11)");
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut sprite_config.frames).clamp_range(1..=256));
+                ui.label("frames");
+                ui.add(egui::DragValue::new(&mut sprite_config.columns).clamp_range(1..=64));
+                ui.label("columns");
+            });
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut sprite_config.frame_width).clamp_range(8..=2048));
+                ui.label("x");
+                ui.add(egui::DragValue::new(&mut sprite_config.frame_height).clamp_range(8..=2048));
+                ui.label("pixels per frame");
+            });
+            ui.horizontal(|ui| {
+                ui.label("file");
+                ui.text_edit_singleline(&mut sprite_config.output_path);
+            });
+            ui.horizontal(|ui| {
+                ui.label("metadata");
+                ui.text_edit_singleline(&mut sprite_config.metadata_path);
+            });
+        });
+    if config != *contact_sheet {
+        *contact_sheet = config;
+    }
+    if sprite_config != *sprite_sheet {
+        *sprite_sheet = sprite_config;
+    }
+}
+
+fn start_contact_sheet(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    config: Res<ContactSheetConfig>,
+    clips: Res<Assets<AnimationClip>>,
+    mut players: Query<&mut AnimationPlayer>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F9) {
+        return;
+    }
+    let Some(mut player) = players.iter_mut().next() else {
+        return;
+    };
+    let Some(clip) = clips.get(player.animation_clip()) else {
+        println!("contact sheet: current clip is not loaded yet");
+        return;
+    };
+
+    let count = config.columns * config.rows;
+    if count == 0 {
+        println!("contact sheet: columns and rows must both be non-zero");
+        return;
+    }
+
+    let was_paused = player.is_paused();
+    let resume_at = player.seek_time();
+    player.pause();
+
+    println!(
+        "contact sheet: capturing {} frames ({}x{})",
+        count, config.columns, config.rows
+    );
+    commands.insert_resource(SheetCapture {
+        kind: SheetKind::Contact,
+        sample_times: sample_times(clip.duration(), count),
+        next_sample: 0,
+        settle: 0,
+        awaiting_screenshot: false,
+        images: Arc::new(Mutex::new(Vec::with_capacity(count as usize))),
+        was_paused,
+        resume_at,
+        saved_cameras: Vec::new(),
+    });
+}
+
+fn start_sprite_sheet(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    config: Res<SpriteSheetConfig>,
+    clips: Res<Assets<AnimationClip>>,
+    animations: Option<Res<Animations>>,
+    animation_meta: Res<AnimationsMetadata>,
+    mut players: Query<&mut AnimationPlayer>,
+    mut cameras: Query<(Entity, &mut Transform), With<Camera3d>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F11) {
+        return;
+    }
+    let (Some(mut player), Some(animations)) = (players.iter_mut().next(), animations) else {
+        return;
+    };
+    let Some(clip) = clips.get(player.animation_clip()) else {
+        println!("sprite sheet: current clip is not loaded yet");
+        return;
+    };
+    if config.frames == 0 || config.columns == 0 {
+        println!("sprite sheet: frames and columns must both be non-zero");
+        return;
+    }
+
+    let was_paused = player.is_paused();
+    let resume_at = player.seek_time();
+    player.pause();
+
+    let mut saved_cameras = Vec::new();
+    if let Some(camera) = config.camera {
+        for (entity, mut transform) in &mut cameras {
+            saved_cameras.push((entity, *transform));
+            *transform = camera;
+        }
+    }
+
+    let clip_name = clip_name(player.animation_clip().id(), &animations, &animation_meta);
+    println!(
+        "sprite sheet: capturing {} frames of {clip_name}",
+        config.frames
+    );
+    commands.insert_resource(SheetCapture {
+        kind: SheetKind::Sprite { clip: clip_name },
+        sample_times: sample_times(clip.duration(), config.frames),
+        next_sample: 0,
+        settle: 0,
+        awaiting_screenshot: false,
+        images: Arc::new(Mutex::new(Vec::with_capacity(config.frames as usize))),
+        was_paused,
+        resume_at,
+        saved_cameras,
+    });
+}
+
+fn step_contact_sheet(
+    mut commands: Commands,
+    mut capture: ResMut<SheetCapture>,
+    config: Res<ContactSheetConfig>,
+    sprite_config: Res<SpriteSheetConfig>,
+    mut players: Query<&mut AnimationPlayer>,
+    mut cameras: Query<&mut Transform, With<Camera3d>>,
+    mut screenshot_manager: ResMut<ScreenshotManager>,
+    window: Query<Entity, With<PrimaryWindow>>,
+) {
+    let captured = capture.images.lock().unwrap().len();
+
+    if capture.awaiting_screenshot {
+        if captured < capture.next_sample {
+            return;
+        }
+        capture.awaiting_screenshot = false;
+    }
+
+    if captured == capture.sample_times.len() {
+        let images = std::mem::take(&mut *capture.images.lock().unwrap());
+        match &capture.kind {
+            SheetKind::Contact => match compose_grid(&images, config.columns) {
+                Ok(sheet) => match sheet.save(&config.output_path) {
+                    Ok(()) => println!("contact sheet: wrote {}", config.output_path),
+                    Err(e) => {
+                        println!("contact sheet: failed to write {}: {e}", config.output_path)
+                    }
+                },
+                Err(e) => println!("contact sheet: {e}"),
+            },
+            SheetKind::Sprite { clip } => {
+                if let Err(e) =
+                    write_sprite_sheet(&images, &capture.sample_times, clip, &sprite_config)
+                {
+                    println!("sprite sheet: {e}");
+                }
+            }
+        }
+
+        for &(entity, transform) in &capture.saved_cameras {
+            if let Ok(mut camera) = cameras.get_mut(entity) {
+                *camera = transform;
+            }
+        }
+        for mut player in &mut players {
+            player.seek_to(capture.resume_at);
+            if !capture.was_paused {
+                player.resume();
+            }
+        }
+        commands.remove_resource::<SheetCapture>();
+        return;
+    }
+
+    if capture.settle > 0 {
+        capture.settle -= 1;
+        if capture.settle == 0 {
+            let Ok(window) = window.get_single() else {
+                return;
+            };
+            let images = capture.images.clone();
+            if screenshot_manager
+                .take_screenshot(window, move |image| images.lock().unwrap().push(image))
+                .is_ok()
+            {
+                capture.next_sample += 1;
+                capture.awaiting_screenshot = true;
+            } else {
+                // A screenshot is already pending for this window; retry next frame.
+                capture.settle = 1;
+            }
+        }
+        return;
+    }
+
+    let t = capture.sample_times[capture.next_sample];
+    for mut player in &mut players {
+        player.seek_to(t);
+    }
+    capture.settle = SETTLE_FRAMES;
+}
+
+fn start_frame_sequence(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    config: Res<FrameSequenceConfig>,
+    clips: Res<Assets<AnimationClip>>,
+    mut players: Query<&mut AnimationPlayer>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F12) {
+        return;
+    }
+    let Some(mut player) = players.iter_mut().next() else {
+        return;
+    };
+    let Some(clip) = clips.get(player.animation_clip()) else {
+        println!("frame sequence: current clip is not loaded yet");
+        return;
+    };
+    if let Err(e) = fs::create_dir_all(&config.directory) {
+        println!("frame sequence: could not create {}: {e}", config.directory);
+        return;
+    }
+
+    let frames = ((clip.duration() * config.fps).round() as u32).max(1);
+    let was_paused = player.is_paused();
+    let resume_at = player.seek_time();
+    player.pause();
+
+    println!(
+        "frame sequence: capturing {frames} frames at {} fps to {}",
+        config.fps, config.directory
+    );
+    commands.insert_resource(FrameSequence {
+        sample_times: (0..frames).map(|i| i as f32 / config.fps).collect(),
+        frame: 0,
+        settle: None,
+        requested: 0,
+        written: Arc::default(),
+        was_paused,
+        resume_at,
+    });
+}
+
+fn step_frame_sequence(
+    mut commands: Commands,
+    mut sequence: ResMut<FrameSequence>,
+    config: Res<FrameSequenceConfig>,
+    mut players: Query<&mut AnimationPlayer>,
+    mut screenshot_manager: ResMut<ScreenshotManager>,
+    window: Query<Entity, With<PrimaryWindow>>,
+) {
+    if *sequence.written.lock().unwrap() < sequence.requested {
+        return;
+    }
+    if sequence.frame >= sequence.sample_times.len() {
+        for mut player in &mut players {
+            player.seek_to(sequence.resume_at);
+            if !sequence.was_paused {
+                player.resume();
+            }
+        }
+        println!(
+            "frame sequence: wrote {} frames to {}",
+            sequence.requested, config.directory
+        );
+        commands.remove_resource::<FrameSequence>();
+        return;
+    }
+
+    match sequence.settle {
+        None => {
+            let t = sequence.sample_times[sequence.frame];
+            for mut player in &mut players {
+                player.seek_to(t);
+            }
+            sequence.settle = Some(SETTLE_FRAMES);
+        }
+        Some(0) => {
+            let Ok(window) = window.get_single() else {
+                return;
+            };
+            let path = format!("{}/frame_{:04}.png", config.directory, sequence.frame);
+            let written = sequence.written.clone();
+            let requested = screenshot_manager.take_screenshot(window, move |image| {
+                match image.try_into_dynamic() {
+                    Ok(dynamic) => {
+                        if let Err(e) = dynamic.to_rgba8().save(&path) {
+                            println!("frame sequence: failed to write {path}: {e}");
+                        }
+                    }
+                    Err(e) => println!("frame sequence: could not convert screenshot: {e:?}"),
+                }
+                *written.lock().unwrap() += 1;
+            });
+            // If a screenshot is already pending for the window, retry next
+            // frame.
+            if requested.is_ok() {
+                sequence.requested += 1;
+                sequence.frame += 1;
+                sequence.settle = None;
+            }
+        }
+        Some(frames) => sequence.settle = Some(frames - 1),
+    }
+}
+
+fn step_batch_export(
+    mut export: ResMut<BatchExport>,
+    animations: Res<Animations>,
+    animation_meta: Res<AnimationsMetadata>,
+    clips: Res<Assets<AnimationClip>>,
+    asset_server: Res<AssetServer>,
+    time: Res<Time<Real>>,
+    mut players: Query<&mut AnimationPlayer>,
+    mut screenshot_manager: ResMut<ScreenshotManager>,
+    window: Query<Entity, With<PrimaryWindow>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if *export.written.lock().unwrap() < export.requested {
+        return;
+    }
+    if export.clip >= animations.0.len() {
+        println!(
+            "export: wrote {} screenshots to {}",
+            export.requested, export.directory
+        );
+        exit.send(AppExit);
+        return;
+    }
+    if export.frame >= export.frames {
+        export.clip += 1;
+        export.frame = 0;
+        return;
+    }
+    let Some(mut player) = players.iter_mut().next() else {
+        return;
+    };
+    let handle = &animations.0[export.clip];
+    let Some(clip) = clips.get(handle) else {
+        let failed = asset_server.get_load_state(handle.id()) == Some(LoadState::Failed);
+        export.load_wait += time.delta_seconds();
+        if failed || export.load_wait > CLIP_LOAD_TIMEOUT {
+            let name = animation_meta
+                .0
+                .get(export.clip)
+                .map_or("?", |params| params.name.as_str());
+            if failed {
+                println!("export: skipping {name}: the clip failed to load");
+            } else {
+                println!("export: skipping {name}: not loaded after {CLIP_LOAD_TIMEOUT}s");
+            }
+            export.clip += 1;
+            export.frame = 0;
+            export.load_wait = 0.0;
+        }
+        return;
+    };
+    export.load_wait = 0.0;
+
+    match export.settle {
+        None => {
+            if player.animation_clip().id() != handle.id() {
+                player.play(handle.clone_weak()).repeat();
+            }
+            player.pause();
+            player.seek_to(sample_times(clip.duration(), export.frames)[export.frame as usize]);
+            export.settle = Some(SETTLE_FRAMES);
+        }
+        Some(0) => {
+            let Ok(window) = window.get_single() else {
+                return;
+            };
+            if export.requested == 0 {
+                if let Err(e) = fs::create_dir_all(&export.directory) {
+                    println!("export: could not create {}: {e}", export.directory);
+                }
+            }
+            let path = format!(
+                "{}/{}_{}.png",
+                export.directory, animation_meta.0[export.clip].name, export.frame
+            );
+            let written = export.written.clone();
+            let requested = screenshot_manager.take_screenshot(window, move |image| {
+                match image.try_into_dynamic() {
+                    Ok(dynamic) => {
+                        if let Err(e) = dynamic.to_rgba8().save(&path) {
+                            println!("export: failed to write {path}: {e}");
+                        }
+                    }
+                    Err(e) => println!("export: could not convert screenshot: {e:?}"),
+                }
+                *written.lock().unwrap() += 1;
+            });
+            // If a screenshot is already pending for the window, retry next
+            // frame.
+            if requested.is_ok() {
+                export.requested += 1;
+                export.frame += 1;
+                export.settle = None;
+            }
+        }
+        Some(frames) => export.settle = Some(frames - 1),
+    }
+}
+
+/// Lays the captured frames out left-to-right, top-to-bottom in a grid with
+/// `columns` cells per row. All frames are assumed to share the first frame's
+/// dimensions.
+pub fn compose_grid(images: &[Image], columns: u32) -> Result<image::RgbaImage, String> {
+    let frames = images
+        .iter()
+        .map(|image| {
+            image
+                .clone()
+                .try_into_dynamic()
+                .map(|dynamic| dynamic.to_rgba8())
+                .map_err(|e| format!("could not convert screenshot: {e:?}"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let Some(first) = frames.first() else {
+        return Err("no frames captured".to_string());
+    };
+    let (cell_w, cell_h) = first.dimensions();
+    let rows = (frames.len() as u32).div_ceil(columns);
+
+    let mut sheet = image::RgbaImage::new(cell_w * columns, cell_h * rows);
+    for (i, frame) in frames.iter().enumerate() {
+        let x = (i as u32 % columns) * cell_w;
+        let y = (i as u32 / columns) * cell_h;
+        image::imageops::overlay(&mut sheet, frame, x as i64, y as i64);
+    }
+    Ok(sheet)
+}
+
+/// Crops and scales the captured frames to the configured frame size, packs
+/// them into a grid and writes the sheet and its metadata.
+fn write_sprite_sheet(
+    images: &[Image],
+    times: &[f32],
+    clip: &str,
+    config: &SpriteSheetConfig,
+) -> Result<(), String> {
+    let (width, height) = (config.frame_width, config.frame_height);
+    let frames = images
+        .iter()
+        .map(|image| {
+            let mut frame = image
+                .clone()
+                .try_into_dynamic()
+                .map_err(|e| format!("could not convert screenshot: {e:?}"))?;
+            let (w, h) = (frame.width(), frame.height());
+            let aspect = width as f32 / height as f32;
+            let (crop_w, crop_h) = if w as f32 / h as f32 > aspect {
+                ((h as f32 * aspect) as u32, h)
+            } else {
+                (w, (w as f32 / aspect) as u32)
+            };
+            let cropped = frame.crop((w - crop_w) / 2, (h - crop_h) / 2, crop_w, crop_h);
+            Ok(cropped
+                .resize_exact(width, height, FilterType::Triangle)
+                .to_rgba8())
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let columns = config.columns;
+    let rows = (frames.len() as u32).div_ceil(columns);
+    let mut sheet = image::RgbaImage::new(width * columns, height * rows);
+    let mut metadata = SpriteSheetMetadata {
+        clip: clip.to_string(),
+        frame_duration: times.get(1).copied().unwrap_or(0.0),
+        frames: Vec::with_capacity(frames.len()),
+    };
+    for (i, (frame, &time)) in frames.iter().zip(times).enumerate() {
+        let x = (i as u32 % columns) * width;
+        let y = (i as u32 / columns) * height;
+        image::imageops::overlay(&mut sheet, frame, x as i64, y as i64);
+        metadata.frames.push(SpriteFrame {
+            x,
+            y,
+            width,
+            height,
+            time,
+        });
+    }
+
+    sheet
+        .save(&config.output_path)
+        .map_err(|e| format!("failed to write {}: {e}", config.output_path))?;
+    let text = ron::ser::to_string_pretty(&metadata, ron::ser::PrettyConfig::default())
+        .map_err(|e| e.to_string())?;
+    fs::write(&config.metadata_path, text)
+        .map_err(|e| format!("failed to write {}: {e}", config.metadata_path))?;
+    println!(
+        "sprite sheet: wrote {} and {}",
+        config.output_path, config.metadata_path
+    );
+    Ok(())
+}
+
+fn spawn_watermark(mut commands: Commands, config: Res<WatermarkConfig>) {
+    let margin = Val::Px(12.0);
+    let mut style = Style {
+        position_type: PositionType::Absolute,
+        ..default()
+    };
+    match config.corner {
+        Corner::TopLeft => (style.top, style.left) = (margin, margin),
+        Corner::TopRight => (style.top, style.right) = (margin, margin),
+        Corner::BottomLeft => (style.bottom, style.left) = (margin, margin),
+        Corner::BottomRight => (style.bottom, style.right) = (margin, margin),
+    }
+    let mut text = TextBundle::from_section(
+        "",
+        TextStyle {
+            font_size: 20.0,
+            color: Color::WHITE,
+            ..default()
+        },
+    )
+    .with_style(style)
+    .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.5));
+    text.visibility = Visibility::Hidden;
+    commands.spawn((text, Watermark));
+}
+
+/// Shows the watermark only while a contact sheet capture or a batch export
+/// is in flight, labelled with the pose being captured. Sprite sheets are
+/// left clean.
+fn update_watermark(
+    config: Res<WatermarkConfig>,
+    capture: Option<Res<SheetCapture>>,
+    export: Option<Res<BatchExport>>,
+    players: Query<&AnimationPlayer>,
+    animations: Option<Res<Animations>>,
+    animation_meta: Res<AnimationsMetadata>,
+    mut watermarks: Query<(&mut Text, &mut Visibility), With<Watermark>>,
+) {
+    let contact_sheet = capture.is_some_and(|capture| capture.kind == SheetKind::Contact);
+    let label = animations
+        .filter(|_| config.enabled && (contact_sheet || export.is_some()))
+        .zip(players.iter().next())
+        .map(|(animations, player)| {
+            let clip = clip_name(player.animation_clip().id(), &animations, &animation_meta);
+            config.format(&clip, player.seek_time())
+        });
+
+    for (mut text, mut visibility) in &mut watermarks {
+        match &label {
+            Some(label) => {
+                *visibility = Visibility::Inherited;
+                if text.sections[0].value != *label {
+                    text.sections[0].value = label.clone();
+                }
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
+/// Applies the exposure lock when a capture starts and restores the camera's
+/// own settings once it ends.
+fn lock_exposure_during_capture(
+    In(capturing): In<bool>,
+    lock: Res<ExposureLock>,
+    mut cameras: Query<(Entity, &mut Tonemapping, &mut ColorGrading), With<Camera3d>>,
+    mut saved: Local<Vec<(Entity, Tonemapping, ColorGrading)>>,
+) {
+    let capturing = capturing && lock.enabled;
+    if capturing && saved.is_empty() {
+        for (entity, mut tonemapping, mut color_grading) in &mut cameras {
+            saved.push((entity, *tonemapping, *color_grading));
+            *tonemapping = lock.tonemapping;
+            *color_grading = lock.color_grading;
+        }
+    } else if !capturing && !saved.is_empty() {
+        for (entity, tonemapping, color_grading) in saved.drain(..) {
+            if let Ok((_, mut current_tonemapping, mut current_grading)) = cameras.get_mut(entity) {
+                *current_tonemapping = tonemapping;
+                *current_grading = color_grading;
+            }
+        }
+    }
+}
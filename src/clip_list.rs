@@ -0,0 +1,524 @@
+//! Panel listing every clip by name, to pick one directly instead of cycling
+//! through them with return, and to pick a second clip to blend in or layer
+//! on top. Dragging a clip by its handle moves it in the list, which is the
+//! order return cycles through, and saves the new order.
+
+use bevy::animation::animation_player;
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+use bevy_inspector_egui::egui;
+
+use crate::compare::SideBySideConfig;
+use crate::poses::LivePoseBlend;
+use crate::sampling::sample_pose;
+use crate::skeleton::Skeleton;
+use crate::ui::keyboard_free;
+use crate::{play_animation, Animations, AnimationsFile, AnimationsMetadata, CurrentAnimation};
+
+/// A second clip blended over the playing one, e.g. to preview a walk to run
+/// blend. The player only drives one clip, so the target is sampled on the
+/// CPU at the same phase as the playing clip and mixed into its pose.
+#[derive(Resource, Debug)]
+pub struct ClipBlend {
+    /// Index into `Animations` of the clip blended in.
+    pub target: Option<usize>,
+    /// 0 shows only the playing clip, 1 only the target.
+    pub weight: f32,
+    pub mode: BlendMode,
+}
+
+impl Default for ClipBlend {
+    fn default() -> Self {
+        Self {
+            target: None,
+            weight: 0.5,
+            mode: BlendMode::Override,
+        }
+    }
+}
+
+/// How `ClipBlend` mixes its target into the playing clip, toggled with `'`
+/// to compare the two semantics on the same pair of clips.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BlendMode {
+    /// Moves the pose towards the target's pose.
+    #[default]
+    Override,
+    /// Adds the target's motion relative to its first frame on top, the way
+    /// `AdditiveLayer` does.
+    Additive,
+}
+
+impl BlendMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            BlendMode::Override => "override",
+            BlendMode::Additive => "additive",
+        }
+    }
+}
+
+/// A clip layered additively over the playing one, e.g. breathing or an aim
+/// offset over locomotion. Its difference from its own first frame is added
+/// to the pose, so at weight 0 the playing clip shows unchanged. Like
+/// `ClipBlend` it's sampled on the CPU, following the playhead, wrapped
+/// to its own length.
+#[derive(Resource, Debug)]
+pub struct AdditiveLayer {
+    /// Index into `Animations` of the additive clip.
+    pub clip: Option<usize>,
+    pub weight: f32,
+}
+
+impl Default for AdditiveLayer {
+    fn default() -> Self {
+        Self {
+            clip: None,
+            weight: 1.0,
+        }
+    }
+}
+
+/// Case-insensitive name filter for the clip list, which return cycles
+/// through as well.
+#[derive(Resource, Debug, Default)]
+pub struct ClipFilter {
+    pub text: String,
+    /// Indices of the clips matching `text`, or `None` when it's empty.
+    pub visible: Option<Vec<usize>>,
+}
+
+impl ClipFilter {
+    pub fn refresh(&mut self, animation_meta: &AnimationsMetadata) {
+        let text = self.text.trim().to_lowercase();
+        self.visible = (!text.is_empty()).then(|| {
+            animation_meta
+                .0
+                .iter()
+                .enumerate()
+                .filter(|(_, params)| params.name.to_lowercase().contains(&text))
+                .map(|(index, _)| index)
+                .collect()
+        });
+    }
+
+    pub fn is_visible(&self, index: usize) -> bool {
+        self.visible
+            .as_ref()
+            .map_or(true, |visible| visible.contains(&index))
+    }
+
+    /// The first visible clip after `current`, wrapping around.
+    pub fn next(&self, current: usize, count: usize) -> Option<usize> {
+        (1..=count)
+            .map(|step| (current + step) % count)
+            .find(|&index| self.is_visible(index))
+    }
+}
+
+/// Where the clip at `index` ends up once the clip at `from` is moved to
+/// `to`.
+pub fn moved_index(index: usize, from: usize, to: usize) -> usize {
+    if index == from {
+        to
+    } else if from < index && index <= to {
+        index - 1
+    } else if to <= index && index < from {
+        index + 1
+    } else {
+        index
+    }
+}
+
+pub struct ClipListPlugin;
+
+impl Plugin for ClipListPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ClipBlend>()
+            .init_resource::<ClipFilter>()
+            .init_resource::<AdditiveLayer>()
+            .add_systems(
+                Update,
+                (
+                    toggle_blend_mode.run_if(keyboard_free),
+                    (clip_list_panel, additive_layer_panel).run_if(resource_exists::<Animations>()),
+                ),
+            )
+            .add_systems(
+                PostUpdate,
+                blend_in_target
+                    .run_if(|blend: Res<ClipBlend>| blend.target.is_some() && blend.weight > 0.0)
+                    .run_if(resource_exists::<Animations>())
+                    .after(animation_player)
+                    .before(TransformSystem::TransformPropagate),
+            )
+            .add_systems(
+                PostUpdate,
+                add_additive_layer
+                    .run_if(|layer: Res<AdditiveLayer>| layer.clip.is_some() && layer.weight > 0.0)
+                    .run_if(resource_exists::<Animations>())
+                    .after(blend_in_target)
+                    .before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+fn toggle_blend_mode(keyboard_input: Res<Input<KeyCode>>, mut blend: ResMut<ClipBlend>) {
+    if keyboard_input.just_pressed(KeyCode::Apostrophe) {
+        blend.mode = match blend.mode {
+            BlendMode::Override => BlendMode::Additive,
+            BlendMode::Additive => BlendMode::Override,
+        };
+        println!("blend mode: {}", blend.mode.label());
+    }
+}
+
+fn clip_list_panel(
+    mut contexts: EguiContexts,
+    mut animations: ResMut<Animations>,
+    mut animation_meta: ResMut<AnimationsMetadata>,
+    animations_file: Res<AnimationsFile>,
+    clips: Res<Assets<AnimationClip>>,
+    live_pose_blend: Res<LivePoseBlend>,
+    mut current_animation: ResMut<CurrentAnimation>,
+    mut blend: ResMut<ClipBlend>,
+    mut layer: ResMut<AdditiveLayer>,
+    mut side_by_side: ResMut<SideBySideConfig>,
+    mut filter: ResMut<ClipFilter>,
+    mut players: Query<&mut AnimationPlayer>,
+    mut dragging: Local<Option<usize>>,
+) {
+    let speed = players.iter().next().map_or(1.0, |player| player.speed());
+    let mut clicked = None;
+    let mut moved = None;
+    egui::Window::new("Animations").show(contexts.ctx_mut(), |ui| {
+        ui.label("click to play, shift-click to blend in, drag ☰ to reorder");
+        ui.horizontal(|ui| {
+            ui.label("filter");
+            if ui.text_edit_singleline(&mut filter.text).changed() {
+                filter.refresh(&animation_meta);
+            }
+            if ui.small_button("x").clicked() {
+                filter.text.clear();
+                filter.refresh(&animation_meta);
+            }
+        });
+        let mut rows = Vec::new();
+        egui::ScrollArea::vertical()
+            .max_height(400.0)
+            .show(ui, |ui| {
+                egui::Grid::new("clip_list").show(ui, |ui| {
+                    for (index, (params, handle)) in
+                        animation_meta.0.iter().zip(&animations.0).enumerate()
+                    {
+                        if !filter.is_visible(index) {
+                            continue;
+                        }
+                        if ui
+                            .add(egui::Label::new("☰").sense(egui::Sense::drag()))
+                            .drag_started()
+                        {
+                            *dragging = Some(index);
+                        }
+                        let selected = index == current_animation.0;
+                        let response = ui.selectable_label(selected, params.name.as_str());
+                        rows.push((index, response.rect));
+                        if response.clicked() {
+                            if ui.input(|input| input.modifiers.shift) {
+                                blend.target = (blend.target != Some(index)).then_some(index);
+                            } else {
+                                clicked = Some(index);
+                            }
+                        }
+                        match clips.get(handle) {
+                            Some(clip) => ui.label(format!("{:.2}s", clip.duration())),
+                            None => ui.label("loading"),
+                        };
+                        if selected {
+                            ui.label(format!("{speed:.2}x"));
+                        } else if blend.target == Some(index) {
+                            ui.label("blend target");
+                        }
+                        ui.end_row();
+                    }
+                });
+            });
+
+        if let Some(from) = *dragging {
+            // The row nearest the pointer is where the dragged clip goes.
+            let drop = ui
+                .input(|input| input.pointer.interact_pos())
+                .and_then(|pointer| {
+                    rows.iter().min_by(|(_, a), (_, b)| {
+                        (a.center().y - pointer.y)
+                            .abs()
+                            .total_cmp(&(b.center().y - pointer.y).abs())
+                    })
+                })
+                .copied();
+            if let Some((_, rect)) = drop {
+                ui.painter()
+                    .rect_stroke(rect.expand(1.0), 2.0, ui.visuals().selection.stroke);
+            }
+            if ui.input(|input| input.pointer.any_released()) {
+                *dragging = None;
+                moved = drop
+                    .map(|(to, _)| (from, to))
+                    .filter(|(from, to)| from != to);
+            }
+        }
+
+        let Some(target) = blend.target else {
+            return;
+        };
+        ui.separator();
+        if target == current_animation.0 {
+            ui.label("the blend target is the playing clip");
+        }
+        ui.add(
+            egui::Slider::new(&mut blend.weight, 0.0..=1.0)
+                .text(format!("weight of {}", animation_meta.0[target].name)),
+        );
+        ui.horizontal(|ui| {
+            ui.label("blend as (')");
+            ui.radio_value(&mut blend.mode, BlendMode::Override, "override");
+            ui.radio_value(&mut blend.mode, BlendMode::Additive, "additive");
+        });
+        if ui.button("stop blending").clicked() {
+            blend.target = None;
+        }
+    });
+
+    if let Some((from, to)) = moved {
+        let params = animation_meta.0.remove(from);
+        animation_meta.0.insert(to, params);
+        let handle = animations.0.remove(from);
+        animations.0.insert(to, handle);
+
+        current_animation.0 = moved_index(current_animation.0, from, to);
+        blend.target = blend.target.map(|index| moved_index(index, from, to));
+        layer.clip = layer.clip.map(|index| moved_index(index, from, to));
+        side_by_side.clip = moved_index(side_by_side.clip, from, to);
+        filter.refresh(&animation_meta);
+
+        println!("moved {} to position {}", animation_meta.0[to].name, to + 1);
+        match &animations_file.0 {
+            Some(path) => match animation_meta.save(path) {
+                Ok(()) => println!("saved the clip order to {path}"),
+                Err(e) => println!("could not save {path}: {e}"),
+            },
+            None => println!("the clip order isn't saved: the list didn't come from a file"),
+        }
+    }
+
+    let Some(index) = clicked else {
+        return;
+    };
+    current_animation.0 = index;
+    for mut player in &mut players {
+        play_animation(&mut player, &animations.0[index], &live_pose_blend);
+    }
+    println!("Playing animation: {}", animation_meta.0[index].name);
+}
+
+fn blend_in_target(
+    blend: Res<ClipBlend>,
+    animations: Res<Animations>,
+    skeleton: Res<Skeleton>,
+    clips: Res<Assets<AnimationClip>>,
+    players: Query<&AnimationPlayer>,
+    mut transforms: Query<&mut Transform>,
+) {
+    let Some(player) = players.iter().next() else {
+        return;
+    };
+    let Some(target_handle) = blend.target.and_then(|target| animations.0.get(target)) else {
+        return;
+    };
+    // Blending a clip with itself would leave the pose unchanged.
+    if target_handle.id() == player.animation_clip().id() {
+        return;
+    }
+    let (Some(base), Some(target)) = (clips.get(player.animation_clip()), clips.get(target_handle))
+    else {
+        return;
+    };
+
+    let phase = player.seek_time() / base.duration().max(f32::EPSILON);
+    let pose = sample_pose(target, &skeleton, phase * target.duration());
+    let weight = blend.weight.min(1.0);
+    match blend.mode {
+        BlendMode::Override => {
+            for (bone, target) in skeleton.bones.iter().zip(pose) {
+                let Ok(mut transform) = transforms.get_mut(bone.entity) else {
+                    continue;
+                };
+                transform.translation = transform.translation.lerp(target.translation, weight);
+                transform.rotation = transform.rotation.slerp(target.rotation, weight);
+                transform.scale = transform.scale.lerp(target.scale, weight);
+            }
+        }
+        BlendMode::Additive => {
+            let reference = sample_pose(target, &skeleton, 0.0);
+            add_pose_difference(&skeleton, &reference, &pose, weight, &mut transforms);
+        }
+    }
+}
+
+/// Adds how far `sample` has moved from `reference`, scaled by `weight`, to
+/// the bones' current pose.
+fn add_pose_difference(
+    skeleton: &Skeleton,
+    reference: &[Transform],
+    sample: &[Transform],
+    weight: f32,
+    transforms: &mut Query<&mut Transform>,
+) {
+    for ((bone, reference), sample) in skeleton.bones.iter().zip(reference).zip(sample) {
+        let Ok(mut transform) = transforms.get_mut(bone.entity) else {
+            continue;
+        };
+        let rotation = reference.rotation.inverse() * sample.rotation;
+        transform.rotation = transform.rotation * Quat::IDENTITY.slerp(rotation, weight);
+        transform.translation += (sample.translation - reference.translation) * weight;
+    }
+}
+
+fn additive_layer_panel(
+    mut contexts: EguiContexts,
+    animations: Res<Animations>,
+    animation_meta: Res<AnimationsMetadata>,
+    live_pose_blend: Res<LivePoseBlend>,
+    mut current_animation: ResMut<CurrentAnimation>,
+    mut layer: ResMut<AdditiveLayer>,
+    mut players: Query<&mut AnimationPlayer>,
+) {
+    let mut base = current_animation.0;
+    let mut additive = layer.clip;
+    let mut weight = layer.weight;
+    let name = |index: usize| animation_meta.0[index].name.as_str();
+    egui::Window::new("Additive layer")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            egui::ComboBox::from_label("base")
+                .selected_text(name(base))
+                .show_ui(ui, |ui| {
+                    for index in 0..animation_meta.0.len() {
+                        ui.selectable_value(&mut base, index, name(index));
+                    }
+                });
+            egui::ComboBox::from_label("additive")
+                .selected_text(additive.map_or("none", name))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut additive, None, "none");
+                    for index in 0..animation_meta.0.len() {
+                        ui.selectable_value(&mut additive, Some(index), name(index));
+                    }
+                });
+            ui.add(egui::Slider::new(&mut weight, 0.0..=1.0).text("weight"));
+        });
+
+    if layer.clip != additive || layer.weight != weight {
+        layer.clip = additive;
+        layer.weight = weight;
+    }
+    if base != current_animation.0 {
+        current_animation.0 = base;
+        for mut player in &mut players {
+            play_animation(&mut player, &animations.0[base], &live_pose_blend);
+        }
+        println!("Playing animation: {}", animation_meta.0[base].name);
+    }
+}
+
+fn add_additive_layer(
+    layer: Res<AdditiveLayer>,
+    animations: Res<Animations>,
+    skeleton: Res<Skeleton>,
+    clips: Res<Assets<AnimationClip>>,
+    players: Query<&AnimationPlayer>,
+    mut transforms: Query<&mut Transform>,
+) {
+    let Some(player) = players.iter().next() else {
+        return;
+    };
+    let Some(clip) = layer
+        .clip
+        .and_then(|index| animations.0.get(index))
+        .and_then(|handle| clips.get(handle))
+    else {
+        return;
+    };
+
+    let time = player
+        .seek_time()
+        .rem_euclid(clip.duration().max(f32::EPSILON));
+    let reference = sample_pose(clip, &skeleton, 0.0);
+    let pose = sample_pose(clip, &skeleton, time);
+    add_pose_difference(
+        &skeleton,
+        &reference,
+        &pose,
+        layer.weight.min(1.0),
+        &mut transforms,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AnimationParams;
+
+    fn metadata(names: &[&str]) -> AnimationsMetadata {
+        AnimationsMetadata(
+            names
+                .iter()
+                .map(|name| AnimationParams::new("clips.glb", name))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn filter_matches_case_insensitively() {
+        let mut filter = ClipFilter {
+            text: " WALK ".to_string(),
+            ..default()
+        };
+        filter.refresh(&metadata(&["Walk", "Run", "walk_fast"]));
+        assert_eq!(filter.visible, Some(vec![0, 2]));
+        assert!(filter.is_visible(2));
+        assert!(!filter.is_visible(1));
+    }
+
+    #[test]
+    fn filter_next_skips_hidden_clips_and_wraps() {
+        let mut filter = ClipFilter {
+            text: "walk".to_string(),
+            ..default()
+        };
+        filter.refresh(&metadata(&["Walk", "Run", "walk_fast"]));
+        assert_eq!(filter.next(0, 3), Some(2));
+        assert_eq!(filter.next(2, 3), Some(0));
+        assert_eq!(filter.next(1, 3), Some(2));
+    }
+
+    #[test]
+    fn empty_filter_shows_everything() {
+        let mut filter = ClipFilter::default();
+        filter.refresh(&metadata(&["Walk", "Run"]));
+        assert_eq!(filter.visible, None);
+        assert_eq!(filter.next(1, 2), Some(0));
+    }
+
+    #[test]
+    fn filter_without_matches_has_no_next() {
+        let mut filter = ClipFilter {
+            text: "swim".to_string(),
+            ..default()
+        };
+        filter.refresh(&metadata(&["Walk", "Run"]));
+        assert_eq!(filter.visible, Some(vec![]));
+        assert_eq!(filter.next(0, 2), None);
+    }
+}
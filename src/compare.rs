@@ -0,0 +1,251 @@
+//! A second copy of the character next to the first, playing another clip,
+//! to compare a tweaked clip against the original.
+//!
+//! The copy's `AnimationPlayer` is removed as soon as its scene spawns, so
+//! the tools built around the one player never see it; its bones are posed
+//! from sampled clips instead.
+
+use bevy::animation::animation_player;
+use bevy::hierarchy::HierarchyQueryExt;
+use bevy::prelude::*;
+use bevy::scene::scene_spawner_system;
+use bevy::transform::TransformSystem;
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+use bevy_inspector_egui::egui;
+
+use crate::poses::LivePoseBlend;
+use crate::sampling::sample_pose;
+use crate::skeleton::Skeleton;
+use crate::{
+    play_animation, Animations, AnimationsMetadata, CurrentAnimation, ModelConfig, ModelRoot,
+};
+
+#[derive(Resource, Debug)]
+pub struct SideBySideConfig {
+    pub enabled: bool,
+    /// Index into `Animations` of the clip the second model plays.
+    pub clip: usize,
+    /// Show the same time on both models. The shorter clip holds its last
+    /// pose until the longer one wraps.
+    pub sync: bool,
+    /// Where the second model stands relative to the first.
+    pub offset: Vec3,
+}
+
+impl Default for SideBySideConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            clip: 0,
+            sync: true,
+            offset: Vec3::new(1.5, 0.0, 0.0),
+        }
+    }
+}
+
+#[derive(Component)]
+struct SideBySideModel;
+
+#[derive(Resource, Default, Debug)]
+struct SideBySide {
+    root: Option<Entity>,
+    /// Entity in the second model that had the animation player, which clip
+    /// paths start from.
+    animated_root: Option<Entity>,
+    /// The second model's entity for each skeleton bone.
+    bones: Vec<Option<Entity>>,
+    /// Playhead of the second model when not synced.
+    elapsed: f32,
+}
+
+pub struct SideBySidePlugin;
+
+impl Plugin for SideBySidePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SideBySideConfig>()
+            .init_resource::<SideBySide>()
+            .add_systems(
+                Update,
+                (side_by_side_panel, spawn_side_by_side)
+                    .chain()
+                    .run_if(resource_exists::<Animations>()),
+            )
+            .add_systems(
+                SpawnScene,
+                strip_animation_player.after(scene_spawner_system),
+            )
+            .add_systems(
+                PostUpdate,
+                pose_side_by_side
+                    .run_if(|side_by_side: Res<SideBySide>| side_by_side.animated_root.is_some())
+                    .run_if(resource_exists::<Animations>())
+                    .after(animation_player)
+                    .before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+fn side_by_side_panel(
+    mut contexts: EguiContexts,
+    mut config: ResMut<SideBySideConfig>,
+    animations: Res<Animations>,
+    animation_meta: Res<AnimationsMetadata>,
+    live_pose_blend: Res<LivePoseBlend>,
+    mut current_animation: ResMut<CurrentAnimation>,
+    mut players: Query<&mut AnimationPlayer>,
+) {
+    let mut left = current_animation.0;
+    egui::Window::new("Side by side")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.checkbox(&mut config.enabled, "show a second model");
+            let name = |index: usize| animation_meta.0[index].name.as_str();
+            for (label, clip) in [("left", &mut left), ("right", &mut config.clip)] {
+                egui::ComboBox::from_label(label)
+                    .selected_text(name(*clip))
+                    .show_ui(ui, |ui| {
+                        for index in 0..animation_meta.0.len() {
+                            ui.selectable_value(clip, index, name(index));
+                        }
+                    });
+            }
+            ui.checkbox(&mut config.sync, "sync playheads");
+            ui.horizontal(|ui| {
+                ui.label("offset");
+                ui.add(egui::DragValue::new(&mut config.offset.x).speed(0.01));
+                ui.add(egui::DragValue::new(&mut config.offset.y).speed(0.01));
+                ui.add(egui::DragValue::new(&mut config.offset.z).speed(0.01));
+            });
+        });
+
+    if left != current_animation.0 {
+        current_animation.0 = left;
+        for mut player in &mut players {
+            play_animation(&mut player, &animations.0[left], &live_pose_blend);
+        }
+        println!("Playing animation: {}", animation_meta.0[left].name);
+    }
+}
+
+/// Spawns or despawns the second model as the config asks, and keeps it
+/// beside the first one.
+fn spawn_side_by_side(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    config: Res<SideBySideConfig>,
+    model_config: Res<ModelConfig>,
+    mut side_by_side: ResMut<SideBySide>,
+    models: Query<&Transform, (With<ModelRoot>, Without<SideBySideModel>)>,
+    mut copies: Query<&mut Transform, With<SideBySideModel>>,
+) {
+    let Some(&model) = models.iter().next() else {
+        return;
+    };
+    let transform = model.with_translation(model.translation + config.offset);
+    match (config.enabled, side_by_side.root) {
+        (true, None) => {
+            let root = commands
+                .spawn((
+                    SceneBundle {
+                        scene: asset_server.load(&model_config.scene),
+                        transform,
+                        ..default()
+                    },
+                    SideBySideModel,
+                ))
+                .id();
+            *side_by_side = SideBySide {
+                root: Some(root),
+                ..default()
+            };
+        }
+        (false, Some(root)) => {
+            commands.entity(root).despawn_recursive();
+            *side_by_side = SideBySide::default();
+        }
+        (true, Some(root)) => {
+            if let Ok(mut copy) = copies.get_mut(root) {
+                *copy = transform;
+            }
+        }
+        (false, None) => {}
+    }
+}
+
+/// Runs right after scenes spawn, before anything else can pick up the second
+/// model's player as the character's.
+fn strip_animation_player(
+    mut commands: Commands,
+    players: Query<Entity, Added<AnimationPlayer>>,
+    parents: Query<&Parent>,
+    models: Query<(), With<SideBySideModel>>,
+    mut side_by_side: ResMut<SideBySide>,
+) {
+    for entity in &players {
+        if parents
+            .iter_ancestors(entity)
+            .any(|ancestor| models.contains(ancestor))
+        {
+            commands.entity(entity).remove::<AnimationPlayer>();
+            side_by_side.animated_root = Some(entity);
+            side_by_side.bones.clear();
+        }
+    }
+}
+
+fn pose_side_by_side(
+    time: Res<Time>,
+    config: Res<SideBySideConfig>,
+    mut side_by_side: ResMut<SideBySide>,
+    skeleton: Res<Skeleton>,
+    animations: Res<Animations>,
+    clips: Res<Assets<AnimationClip>>,
+    players: Query<&AnimationPlayer>,
+    children: Query<&Children>,
+    names: Query<&Name>,
+    mut transforms: Query<&mut Transform>,
+) {
+    let Some(animated_root) = side_by_side.animated_root else {
+        return;
+    };
+    if side_by_side.bones.len() != skeleton.bones.len() {
+        side_by_side.bones = skeleton
+            .bones
+            .iter()
+            .map(|bone| {
+                children.iter_descendants(animated_root).find(|&entity| {
+                    names
+                        .get(entity)
+                        .is_ok_and(|name| name.as_str() == bone.name)
+                })
+            })
+            .collect();
+    }
+    let Some(player) = players.iter().next() else {
+        return;
+    };
+    let Some(clip) = animations
+        .0
+        .get(config.clip)
+        .and_then(|handle| clips.get(handle))
+    else {
+        return;
+    };
+
+    let duration = clip.duration();
+    let time = if config.sync {
+        player.seek_time().min(duration)
+    } else {
+        if !player.is_paused() {
+            side_by_side.elapsed += time.delta_seconds() * player.speed();
+        }
+        side_by_side.elapsed.rem_euclid(duration.max(f32::EPSILON))
+    };
+    let pose = sample_pose(clip, &skeleton, time);
+    for (entity, pose) in side_by_side.bones.iter().zip(pose) {
+        let Some(mut transform) = entity.and_then(|entity| transforms.get_mut(entity).ok()) else {
+            continue;
+        };
+        *transform = pose;
+    }
+}
@@ -0,0 +1,272 @@
+//! Sanity checks run against animation clips as they finish loading.
+
+use bevy::animation::{EntityPath, Interpolation, Keyframes, VariableCurve};
+use bevy::prelude::*;
+use bevy::reflect::Struct;
+use bevy::utils::{HashMap, HashSet};
+
+use crate::skeleton::Skeleton;
+use crate::{Animations, AnimationsMetadata};
+
+/// Adjacent rotation keys whose quaternion dot product falls below this are
+/// reported as a sign flip: interpolating between them spins the bone the long
+/// way around.
+pub const FLIP_DOT_THRESHOLD: f32 = -0.5;
+
+pub struct ClipDiagnosticsPlugin;
+
+impl Plugin for ClipDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (report_clip_issues, report_unmatched_targets));
+    }
+}
+
+/// The clip's bone paths paired with their curve index. `AnimationClip` keeps
+/// this map private, so it is read back through reflection.
+pub fn clip_paths(clip: &AnimationClip) -> Vec<(&EntityPath, usize)> {
+    clip.field("paths")
+        .and_then(|paths| paths.downcast_ref::<HashMap<EntityPath, usize>>())
+        .map(|paths| paths.iter().map(|(path, index)| (path, *index)).collect())
+        .unwrap_or_default()
+}
+
+pub fn path_string(path: &EntityPath) -> String {
+    path.parts
+        .iter()
+        .map(|name| name.as_str())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// The last element of the path, i.e. the bone the curves drive.
+pub fn path_bone_name(path: &EntityPath) -> &str {
+    path.parts.last().map(|name| name.as_str()).unwrap_or("")
+}
+
+/// Keyframe values of a rotation curve, with cubic spline tangents stripped.
+pub fn rotation_keys(curve: &VariableCurve) -> Option<Vec<Quat>> {
+    let Keyframes::Rotation(rotations) = &curve.keyframes else {
+        return None;
+    };
+    Some(match curve.interpolation {
+        Interpolation::CubicSpline => rotations.iter().skip(1).step_by(3).copied().collect(),
+        _ => rotations.clone(),
+    })
+}
+
+#[derive(Debug)]
+pub struct RotationFlip {
+    pub bone: String,
+    pub time: f32,
+    pub dot: f32,
+}
+
+/// Finds consecutive rotation keys that take the long way around.
+pub fn find_rotation_flips(clip: &AnimationClip) -> Vec<RotationFlip> {
+    let mut flips = Vec::new();
+    for (path, index) in clip_paths(clip) {
+        let Some(curves) = clip.get_curves(index) else {
+            continue;
+        };
+        for curve in curves {
+            let Some(keys) = rotation_keys(curve) else {
+                continue;
+            };
+            for (i, pair) in keys.windows(2).enumerate() {
+                let dot = pair[0].dot(pair[1]);
+                if dot < FLIP_DOT_THRESHOLD {
+                    flips.push(RotationFlip {
+                        bone: path_bone_name(path).to_string(),
+                        time: curve.keyframe_timestamps.get(i + 1).copied().unwrap_or(0.0),
+                        dot,
+                    });
+                }
+            }
+        }
+    }
+    flips.sort_by(|a, b| a.time.total_cmp(&b.time));
+    flips
+}
+
+/// Differences smaller than this between the last keyframe and the clip
+/// duration are treated as rounding.
+pub const DURATION_TOLERANCE: f32 = 1e-4;
+
+/// Time of the latest keyframe across all of the clip's curves.
+pub fn last_keyframe_time(clip: &AnimationClip) -> f32 {
+    clip.curves()
+        .iter()
+        .flatten()
+        .filter_map(|curve| curve.keyframe_timestamps.last().copied())
+        .fold(0.0, f32::max)
+}
+
+/// Signed gap between the last keyframe and the clip's stated duration, if it
+/// is large enough to matter. Positive means keys past the end that get cut
+/// off when the clip loops.
+pub fn duration_mismatch(clip: &AnimationClip) -> Option<f32> {
+    let overshoot = last_keyframe_time(clip) - clip.duration();
+    (overshoot.abs() > DURATION_TOLERANCE).then_some(overshoot)
+}
+
+/// Clips shorter than this are reported as having no duration at all.
+pub const ZERO_DURATION: f32 = 1e-4;
+
+/// Rotation differences between a curve's first and last key above this many
+/// degrees show up as a pop when the clip loops.
+pub const LOOP_SEAM_DEGREES: f32 = 5.0;
+
+#[derive(Debug)]
+pub struct LoopSeam {
+    pub bone: String,
+    pub degrees: f32,
+}
+
+/// The bone whose rotation jumps furthest from the last key back to the first
+/// when the clip loops, if the jump is large enough to notice.
+pub fn worst_loop_seam(clip: &AnimationClip) -> Option<LoopSeam> {
+    let mut worst: Option<LoopSeam> = None;
+    for (path, index) in clip_paths(clip) {
+        for curve in clip.get_curves(index).into_iter().flatten() {
+            let Some(keys) = rotation_keys(curve) else {
+                continue;
+            };
+            let (Some(first), Some(last)) = (keys.first(), keys.last()) else {
+                continue;
+            };
+            let degrees = first.angle_between(*last).to_degrees();
+            if degrees
+                > worst
+                    .as_ref()
+                    .map_or(LOOP_SEAM_DEGREES, |seam| seam.degrees)
+            {
+                worst = Some(LoopSeam {
+                    bone: path_bone_name(path).to_string(),
+                    degrees,
+                });
+            }
+        }
+    }
+    worst
+}
+
+/// Curve targets that don't resolve to any node in the skeleton. These tracks
+/// are silently ignored by the animation player.
+pub fn find_unmatched_targets(
+    clip: &AnimationClip,
+    node_paths: &HashSet<EntityPath>,
+) -> Vec<String> {
+    let mut unmatched: Vec<String> = clip_paths(clip)
+        .into_iter()
+        .filter(|(path, _)| !node_paths.contains(*path))
+        .map(|(path, _)| path_string(path))
+        .collect();
+    unmatched.sort();
+    unmatched
+}
+
+/// Display name for a clip handle, falling back to the asset id for clips that
+/// aren't listed in the metadata.
+pub fn clip_name(
+    id: AssetId<AnimationClip>,
+    animations: &Animations,
+    animation_meta: &AnimationsMetadata,
+) -> String {
+    animations
+        .0
+        .iter()
+        .position(|handle| handle.id() == id)
+        .and_then(|index| animation_meta.0.get(index))
+        .map(|params| params.name.clone())
+        .unwrap_or_else(|| format!("{id:?}"))
+}
+
+fn report_clip_issues(
+    mut events: EventReader<AssetEvent<AnimationClip>>,
+    clips: Res<Assets<AnimationClip>>,
+    animations: Option<Res<Animations>>,
+    animation_meta: Res<AnimationsMetadata>,
+) {
+    let Some(animations) = animations else {
+        return;
+    };
+    for event in events.read() {
+        let AssetEvent::LoadedWithDependencies { id } = event else {
+            continue;
+        };
+        let Some(clip) = clips.get(*id) else {
+            continue;
+        };
+        let name = clip_name(*id, &animations, &animation_meta);
+
+        if clip.duration() < ZERO_DURATION {
+            println!("[{name}] clip has zero duration");
+        }
+
+        if let Some(seam) = worst_loop_seam(clip) {
+            println!(
+                "[{name}] loop seam: {} jumps {:.1} degrees from the last key to the first",
+                seam.bone, seam.degrees
+            );
+        }
+
+        if let Some(overshoot) = duration_mismatch(clip) {
+            println!(
+                "[{name}] last keyframe at {:.4}s but clip duration is {:.4}s ({overshoot:+.4}s)",
+                last_keyframe_time(clip),
+                clip.duration()
+            );
+        }
+
+        let flips = find_rotation_flips(clip);
+        if !flips.is_empty() {
+            println!("[{name}] {} rotation sign flip(s):", flips.len());
+            for flip in flips {
+                println!(
+                    "    {} at {:.3}s (dot {:.2})",
+                    flip.bone, flip.time, flip.dot
+                );
+            }
+        }
+    }
+}
+
+fn report_unmatched_targets(
+    clips: Res<Assets<AnimationClip>>,
+    skeleton: Res<Skeleton>,
+    animations: Option<Res<Animations>>,
+    animation_meta: Res<AnimationsMetadata>,
+    mut validated: Local<HashSet<AssetId<AnimationClip>>>,
+) {
+    let Some(animations) = animations else {
+        return;
+    };
+    if skeleton.is_changed() {
+        validated.clear();
+    }
+    if skeleton.root.is_none() {
+        return;
+    }
+
+    for handle in &animations.0 {
+        if validated.contains(&handle.id()) {
+            continue;
+        }
+        let Some(clip) = clips.get(handle) else {
+            continue;
+        };
+        validated.insert(handle.id());
+
+        let unmatched = find_unmatched_targets(clip, &skeleton.node_paths);
+        if !unmatched.is_empty() {
+            let name = clip_name(handle.id(), &animations, &animation_meta);
+            println!(
+                "[{name}] {} animation target(s) not found in the skeleton:",
+                unmatched.len()
+            );
+            for path in unmatched {
+                println!("    {path}");
+            }
+        }
+    }
+}
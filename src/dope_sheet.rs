@@ -0,0 +1,131 @@
+//! Dope sheet of the playing clip: one row per animated bone with a dot at
+//! each of its keyframes, to see how the clip is keyed at a glance.
+
+use bevy::prelude::*;
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+use bevy_inspector_egui::egui;
+
+use crate::diagnostics::{clip_paths, path_bone_name};
+use crate::skeleton::Skeleton;
+
+const ROW_HEIGHT: f32 = 14.0;
+const LABEL_WIDTH: f32 = 140.0;
+const SHEET_WIDTH: f32 = 420.0;
+/// How close in pixels a click has to be to a key to seek to it.
+const PICK_DISTANCE: f32 = 6.0;
+
+/// Keyframe times of one animated bone, across all of its curves.
+#[derive(Debug, Clone)]
+pub struct DopeSheetRow {
+    pub bone: String,
+    pub times: Vec<f32>,
+}
+
+/// Rows for every bone `clip` animates, in skeleton order.
+pub fn dope_sheet_rows(clip: &AnimationClip, skeleton: &Skeleton) -> Vec<DopeSheetRow> {
+    let mut rows: Vec<(usize, DopeSheetRow)> = clip_paths(clip)
+        .into_iter()
+        .map(|(path, index)| {
+            let bone = path_bone_name(path).to_string();
+            let mut times: Vec<f32> = clip
+                .get_curves(index)
+                .into_iter()
+                .flatten()
+                .flat_map(|curve| curve.keyframe_timestamps.iter().copied())
+                .collect();
+            times.sort_by(f32::total_cmp);
+            times.dedup();
+            let order = skeleton.index_of(&bone).unwrap_or(usize::MAX);
+            (order, DopeSheetRow { bone, times })
+        })
+        .collect();
+    rows.sort_by(|(a, x), (b, y)| a.cmp(b).then_with(|| x.bone.cmp(&y.bone)));
+    rows.into_iter().map(|(_, row)| row).collect()
+}
+
+pub struct DopeSheetPlugin;
+
+impl Plugin for DopeSheetPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, dope_sheet_panel);
+    }
+}
+
+fn dope_sheet_panel(
+    mut contexts: EguiContexts,
+    skeleton: Res<Skeleton>,
+    clips: Res<Assets<AnimationClip>>,
+    mut players: Query<&mut AnimationPlayer>,
+) {
+    let Some(mut player) = players.iter_mut().next() else {
+        return;
+    };
+    let Some(clip) = clips.get(player.animation_clip()) else {
+        return;
+    };
+    let duration = clip.duration().max(f32::EPSILON);
+    let rows = dope_sheet_rows(clip, &skeleton);
+    let playhead = player.seek_time();
+    let mut seek = None;
+
+    egui::Window::new("Dope sheet")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label(format!("{} animated bones, {duration:.3}s", rows.len()));
+            egui::ScrollArea::vertical()
+                .max_height(400.0)
+                .show(ui, |ui| {
+                    for row in &rows {
+                        ui.horizontal(|ui| {
+                            ui.add_sized(
+                                [LABEL_WIDTH, ROW_HEIGHT],
+                                egui::Label::new(row.bone.as_str()).truncate(true),
+                            );
+                            let (rect, response) = ui.allocate_exact_size(
+                                egui::vec2(SHEET_WIDTH, ROW_HEIGHT),
+                                egui::Sense::click(),
+                            );
+                            let x = |time: f32| rect.left() + time / duration * rect.width();
+                            let painter = ui.painter_at(rect);
+                            painter.line_segment(
+                                [rect.left_center(), rect.right_center()],
+                                egui::Stroke::new(1.0, egui::Color32::DARK_GRAY),
+                            );
+                            for &time in &row.times {
+                                painter.circle_filled(
+                                    egui::pos2(x(time), rect.center().y),
+                                    3.0,
+                                    egui::Color32::LIGHT_BLUE,
+                                );
+                            }
+                            painter.line_segment(
+                                [
+                                    egui::pos2(x(playhead), rect.top()),
+                                    egui::pos2(x(playhead), rect.bottom()),
+                                ],
+                                egui::Stroke::new(1.0, egui::Color32::RED),
+                            );
+
+                            if let Some(pointer) = response
+                                .clicked()
+                                .then(|| response.interact_pointer_pos())
+                                .flatten()
+                            {
+                                seek = row
+                                    .times
+                                    .iter()
+                                    .copied()
+                                    .map(|time| (time, (x(time) - pointer.x).abs()))
+                                    .filter(|&(_, distance)| distance <= PICK_DISTANCE)
+                                    .min_by(|a, b| a.1.total_cmp(&b.1))
+                                    .map(|(time, _)| time);
+                            }
+                        });
+                    }
+                });
+        });
+
+    if let Some(time) = seek {
+        player.seek_to(time);
+    }
+}
@@ -0,0 +1,133 @@
+//! Timed gameplay events attached to clips, previewed as playback crosses them.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{Animations, AnimationsMetadata};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum EventKind {
+    PlaySound(String),
+    SpawnEffect(String),
+}
+
+impl EventKind {
+    pub fn color(&self) -> Color {
+        match self {
+            EventKind::PlaySound(_) => Color::CYAN,
+            EventKind::SpawnEffect(_) => Color::ORANGE,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            EventKind::PlaySound(sound) => format!("play sound '{sound}'"),
+            EventKind::SpawnEffect(effect) => format!("spawn effect '{effect}'"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AnimationEvent {
+    /// Seconds from the start of the clip.
+    pub time: f32,
+    pub kind: EventKind,
+}
+
+/// How long a fired event stays visible.
+const FLASH_SECONDS: f32 = 0.4;
+
+struct Flash {
+    color: Color,
+    remaining: f32,
+}
+
+#[derive(Resource, Default)]
+struct EventFlashes(Vec<Flash>);
+
+pub struct AnimationEventsPlugin;
+
+impl Plugin for AnimationEventsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EventFlashes>().add_systems(
+            Update,
+            (
+                fire_crossed_events.run_if(resource_exists::<Animations>()),
+                draw_event_flashes,
+            )
+                .chain(),
+        );
+    }
+}
+
+/// Whether `time` was passed while moving from `previous` to `current`,
+/// accounting for the clip wrapping around in either direction.
+pub fn crossed(time: f32, previous: f32, current: f32, forward: bool) -> bool {
+    match (forward, current >= previous) {
+        (true, true) => previous < time && time <= current,
+        (true, false) => time > previous || time <= current,
+        (false, false) => current <= time && time < previous,
+        (false, true) => time < previous || time >= current,
+    }
+}
+
+fn fire_crossed_events(
+    players: Query<&AnimationPlayer>,
+    animations: Res<Animations>,
+    animation_meta: Res<AnimationsMetadata>,
+    mut flashes: ResMut<EventFlashes>,
+    mut previous: Local<Option<(AssetId<AnimationClip>, f32)>>,
+) {
+    let Some(player) = players.iter().next() else {
+        return;
+    };
+    let clip = player.animation_clip().id();
+    let current = player.seek_time();
+    let last = previous.replace((clip, current));
+
+    // Nothing is crossed on the first frame of a clip or while paused, where
+    // any movement comes from seeking.
+    let Some((last_clip, last_time)) = last else {
+        return;
+    };
+    if last_clip != clip || player.is_paused() || last_time == current {
+        return;
+    }
+
+    let Some(params) = animations
+        .0
+        .iter()
+        .position(|handle| handle.id() == clip)
+        .and_then(|index| animation_meta.0.get(index))
+    else {
+        return;
+    };
+    for event in &params.events {
+        if crossed(event.time, last_time, current, player.speed() >= 0.0) {
+            println!(
+                "[{}] event at {:.3}s: {}",
+                params.name,
+                event.time,
+                event.kind.describe()
+            );
+            flashes.0.push(Flash {
+                color: event.kind.color(),
+                remaining: FLASH_SECONDS,
+            });
+        }
+    }
+}
+
+fn draw_event_flashes(mut gizmos: Gizmos, time: Res<Time>, mut flashes: ResMut<EventFlashes>) {
+    for flash in &mut flashes.0 {
+        let progress = 1.0 - flash.remaining / FLASH_SECONDS;
+        gizmos.circle(
+            Vec3::ZERO,
+            Vec3::Y,
+            0.3 + progress * 0.7,
+            flash.color.with_a(1.0 - progress),
+        );
+        flash.remaining -= time.delta_seconds();
+    }
+    flashes.0.retain(|flash| flash.remaining > 0.0);
+}
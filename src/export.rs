@@ -0,0 +1,139 @@
+//! Exports the character's deformed mesh at the current frame as geometry.
+
+use std::fmt::Write as _;
+use std::fs;
+
+use crate::ui::keyboard_free;
+use bevy::prelude::*;
+use bevy::render::mesh::skinning::SkinnedMeshInverseBindposes;
+use bevy::render::mesh::VertexAttributeValues;
+
+/// Destination of the OBJ written by `F10`.
+#[derive(Resource, Debug)]
+pub struct ObjExportConfig {
+    pub output_path: String,
+}
+
+impl Default for ObjExportConfig {
+    fn default() -> Self {
+        Self {
+            output_path: "pose.obj".to_string(),
+        }
+    }
+}
+
+pub struct ObjExportPlugin;
+
+impl Plugin for ObjExportPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ObjExportConfig>()
+            .add_systems(Update, export_posed_obj.run_if(keyboard_free));
+    }
+}
+
+/// World-space vertex positions of a skinned mesh, deformed on the CPU the
+/// same way the skinning shader does it.
+pub fn skin_positions(
+    mesh: &Mesh,
+    skin: &SkinnedMesh,
+    inverse_bindposes: &[Mat4],
+    global_transforms: &Query<&GlobalTransform>,
+) -> Option<Vec<Vec3>> {
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        return None;
+    };
+    let Some(VertexAttributeValues::Uint16x4(joint_indices)) =
+        mesh.attribute(Mesh::ATTRIBUTE_JOINT_INDEX)
+    else {
+        return None;
+    };
+    let Some(VertexAttributeValues::Float32x4(joint_weights)) =
+        mesh.attribute(Mesh::ATTRIBUTE_JOINT_WEIGHT)
+    else {
+        return None;
+    };
+
+    let joint_matrices = skin
+        .joints
+        .iter()
+        .zip(inverse_bindposes)
+        .map(|(&joint, inverse_bindpose)| {
+            Some(global_transforms.get(joint).ok()?.compute_matrix() * *inverse_bindpose)
+        })
+        .collect::<Option<Vec<Mat4>>>()?;
+
+    positions
+        .iter()
+        .zip(joint_indices)
+        .zip(joint_weights)
+        .map(|((&position, indices), weights)| {
+            let position = Vec3::from(position);
+            let mut skinned = Vec3::ZERO;
+            for (&index, &weight) in indices.iter().zip(weights) {
+                skinned += joint_matrices
+                    .get(index as usize)?
+                    .transform_point3(position)
+                    * weight;
+            }
+            Some(skinned)
+        })
+        .collect()
+}
+
+fn export_posed_obj(
+    keyboard_input: Res<Input<KeyCode>>,
+    config: Res<ObjExportConfig>,
+    meshes: Res<Assets<Mesh>>,
+    inverse_bindposes: Res<Assets<SkinnedMeshInverseBindposes>>,
+    skinned_meshes: Query<(Option<&Name>, &Handle<Mesh>, &SkinnedMesh)>,
+    global_transforms: Query<&GlobalTransform>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F10) {
+        return;
+    }
+
+    let mut obj = String::new();
+    let mut vertex_offset = 1;
+    for (name, mesh, skin) in &skinned_meshes {
+        let Some(mesh) = meshes.get(mesh) else {
+            continue;
+        };
+        let Some(bindposes) = inverse_bindposes.get(&skin.inverse_bindposes) else {
+            continue;
+        };
+        let Some(positions) = skin_positions(mesh, skin, bindposes, &global_transforms) else {
+            println!("obj export: skipping a mesh without skinning attributes");
+            continue;
+        };
+
+        let name = name.map(|name| name.as_str()).unwrap_or("mesh");
+        let _ = writeln!(obj, "o {name}");
+        for p in &positions {
+            let _ = writeln!(obj, "v {} {} {}", p.x, p.y, p.z);
+        }
+        if let Some(indices) = mesh.indices() {
+            let indices: Vec<usize> = indices.iter().collect();
+            for face in indices.chunks_exact(3) {
+                let _ = writeln!(
+                    obj,
+                    "f {} {} {}",
+                    face[0] + vertex_offset,
+                    face[1] + vertex_offset,
+                    face[2] + vertex_offset
+                );
+            }
+        }
+        vertex_offset += positions.len();
+    }
+
+    if vertex_offset == 1 {
+        println!("obj export: no skinned meshes to export");
+        return;
+    }
+    match fs::write(&config.output_path, obj) {
+        Ok(()) => println!("posed mesh written to {}", config.output_path),
+        Err(e) => println!("could not write {}: {e}", config.output_path),
+    }
+}
@@ -0,0 +1,295 @@
+//! Ghost skeletons of the current clip, sampled independently of the player
+//! and drawn over the character for side-by-side comparisons.
+
+use bevy::prelude::*;
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+use bevy_inspector_egui::egui;
+
+use crate::sampling::{draw_pose, pose_world, sample_pose};
+use crate::skeleton::Skeleton;
+use crate::ui::keyboard_free;
+
+/// Plays the current clip at two speeds from the same start, showing how far
+/// the poses drift apart as the faster one runs ahead.
+#[derive(Resource, Debug)]
+pub struct SpeedCompareConfig {
+    pub enabled: bool,
+    pub speeds: [f32; 2],
+}
+
+impl Default for SpeedCompareConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            speeds: [1.0, 1.25],
+        }
+    }
+}
+
+const GHOST_COLORS: [Color; 2] = [Color::AQUAMARINE, Color::GOLD];
+
+#[derive(Resource, Default, Debug)]
+struct SpeedCompare {
+    clip: Option<AssetId<AnimationClip>>,
+    elapsed: f32,
+    /// Mean distance between matching bones this frame, and the largest seen
+    /// since the comparison started.
+    drift: f32,
+    max_drift: f32,
+}
+
+/// Overlays the current clip with its left/right mirror image to show how
+/// asymmetric a supposedly symmetric motion is.
+#[derive(Resource, Debug)]
+pub struct MirrorCompareConfig {
+    pub enabled: bool,
+    /// Fraction of the clip the mirrored ghost runs ahead by; `0.5` lines up
+    /// the opposite steps of a gait cycle.
+    pub phase: f32,
+}
+
+impl Default for MirrorCompareConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            phase: 0.0,
+        }
+    }
+}
+
+#[derive(Resource, Default, Debug)]
+struct MirrorCompare {
+    clip: Option<AssetId<AnimationClip>>,
+    /// Mean distance between each bone and its mirrored counterpart this
+    /// frame.
+    asymmetry: f32,
+    /// Running average of `asymmetry` since the clip started.
+    score: f32,
+    samples: u32,
+}
+
+pub struct GhostsPlugin;
+
+impl Plugin for GhostsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpeedCompareConfig>()
+            .init_resource::<SpeedCompare>()
+            .init_resource::<MirrorCompareConfig>()
+            .init_resource::<MirrorCompare>()
+            .add_systems(
+                Update,
+                (
+                    toggle_speed_compare.run_if(keyboard_free),
+                    (speed_compare_panel, draw_speed_compare)
+                        .run_if(|config: Res<SpeedCompareConfig>| config.enabled),
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Update,
+                (
+                    toggle_mirror_compare.run_if(keyboard_free),
+                    (mirror_compare_panel, draw_mirror_compare)
+                        .run_if(|config: Res<MirrorCompareConfig>| config.enabled),
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn toggle_speed_compare(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut config: ResMut<SpeedCompareConfig>,
+    mut compare: ResMut<SpeedCompare>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F8) {
+        config.enabled = !config.enabled;
+        *compare = SpeedCompare::default();
+        println!("speed comparison ghosts: {}", config.enabled);
+    }
+}
+
+fn speed_compare_panel(
+    mut contexts: EguiContexts,
+    mut config: ResMut<SpeedCompareConfig>,
+    mut compare: ResMut<SpeedCompare>,
+) {
+    egui::Window::new("Speed comparison")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            for (speed, name) in config.speeds.iter_mut().zip(["first", "second"]) {
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::DragValue::new(speed)
+                            .speed(0.01)
+                            .clamp_range(0.01..=10.0),
+                    );
+                    ui.label(format!("{name} ghost speed"));
+                });
+            }
+            ui.label(format!(
+                "drift {:.3} (max {:.3})",
+                compare.drift, compare.max_drift
+            ));
+            if ui.button("restart").clicked() {
+                compare.elapsed = 0.0;
+                compare.max_drift = 0.0;
+            }
+        });
+}
+
+fn draw_speed_compare(
+    mut gizmos: Gizmos,
+    time: Res<Time>,
+    config: Res<SpeedCompareConfig>,
+    mut compare: ResMut<SpeedCompare>,
+    skeleton: Res<Skeleton>,
+    clips: Res<Assets<AnimationClip>>,
+    players: Query<&AnimationPlayer>,
+    parents: Query<&Parent>,
+    global_transforms: Query<&GlobalTransform>,
+) {
+    let Some(player) = players.iter().next() else {
+        return;
+    };
+    let handle = player.animation_clip();
+    let Some(clip) = clips.get(handle) else {
+        return;
+    };
+    if compare.clip != Some(handle.id()) {
+        *compare = SpeedCompare {
+            clip: Some(handle.id()),
+            ..default()
+        };
+    }
+    compare.elapsed += time.delta_seconds();
+
+    let duration = clip.duration().max(f32::EPSILON);
+    let ghosts = config.speeds.map(|speed| {
+        let pose = sample_pose(clip, &skeleton, (compare.elapsed * speed) % duration);
+        pose_world(&skeleton, &pose, &parents, &global_transforms)
+    });
+    for (ghost, color) in ghosts.iter().zip(GHOST_COLORS) {
+        draw_pose(&mut gizmos, &skeleton, ghost, color);
+    }
+
+    let mut total = 0.0;
+    for (a, b) in ghosts[0].iter().zip(&ghosts[1]) {
+        gizmos.line(a.translation, b.translation, Color::WHITE.with_a(0.5));
+        total += a.translation.distance(b.translation);
+    }
+    compare.drift = total / skeleton.bones.len().max(1) as f32;
+    compare.max_drift = compare.max_drift.max(compare.drift);
+}
+
+fn toggle_mirror_compare(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut config: ResMut<MirrorCompareConfig>,
+    mut compare: ResMut<MirrorCompare>,
+) {
+    if keyboard_input.just_pressed(KeyCode::V) {
+        config.enabled = !config.enabled;
+        *compare = MirrorCompare::default();
+        println!("mirror comparison: {}", config.enabled);
+    }
+}
+
+fn mirror_compare_panel(
+    mut contexts: EguiContexts,
+    mut config: ResMut<MirrorCompareConfig>,
+    mut compare: ResMut<MirrorCompare>,
+) {
+    egui::Window::new("Mirror comparison")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                let changed = ui
+                    .add(
+                        egui::DragValue::new(&mut config.phase)
+                            .speed(0.01)
+                            .clamp_range(0.0..=1.0),
+                    )
+                    .changed();
+                ui.label("phase");
+                if changed {
+                    compare.score = 0.0;
+                    compare.samples = 0;
+                }
+            });
+            ui.label(format!(
+                "asymmetry {:.3} (average {:.3})",
+                compare.asymmetry, compare.score
+            ));
+        });
+}
+
+fn draw_mirror_compare(
+    mut gizmos: Gizmos,
+    config: Res<MirrorCompareConfig>,
+    mut compare: ResMut<MirrorCompare>,
+    skeleton: Res<Skeleton>,
+    clips: Res<Assets<AnimationClip>>,
+    players: Query<&AnimationPlayer>,
+    parents: Query<&Parent>,
+    global_transforms: Query<&GlobalTransform>,
+) {
+    let (Some(player), Some(root)) = (players.iter().next(), skeleton.root) else {
+        return;
+    };
+    let handle = player.animation_clip();
+    let Some(clip) = clips.get(handle) else {
+        return;
+    };
+    let Ok(root_global) = global_transforms.get(root) else {
+        return;
+    };
+    if compare.clip != Some(handle.id()) {
+        *compare = MirrorCompare {
+            clip: Some(handle.id()),
+            ..default()
+        };
+    }
+
+    let duration = clip.duration().max(f32::EPSILON);
+    let time = player.seek_time();
+    let original = pose_world(
+        &skeleton,
+        &sample_pose(clip, &skeleton, time),
+        &parents,
+        &global_transforms,
+    );
+    let ahead = pose_world(
+        &skeleton,
+        &sample_pose(clip, &skeleton, (time + config.phase * duration) % duration),
+        &parents,
+        &global_transforms,
+    );
+
+    // Reflect across the character's sagittal plane, i.e. negate x in the
+    // root's space, and swap each bone with its other-side counterpart.
+    let to_world = root_global.compute_matrix();
+    let to_root = to_world.inverse();
+    let mirrored: Vec<Vec3> = (0..skeleton.bones.len())
+        .map(|i| {
+            let p = to_root.transform_point3(ahead[skeleton.mirror_of(i)].translation);
+            to_world.transform_point3(Vec3::new(-p.x, p.y, p.z))
+        })
+        .collect();
+
+    draw_pose(&mut gizmos, &skeleton, &original, GHOST_COLORS[0]);
+    let mut total = 0.0;
+    for (i, bone) in skeleton.bones.iter().enumerate() {
+        if let Some(parent) = bone.parent {
+            gizmos.line(mirrored[parent], mirrored[i], GHOST_COLORS[1]);
+        }
+        gizmos.line(
+            original[i].translation,
+            mirrored[i],
+            Color::WHITE.with_a(0.5),
+        );
+        total += original[i].translation.distance(mirrored[i]);
+    }
+    compare.asymmetry = total / skeleton.bones.len().max(1) as f32;
+    compare.samples += 1;
+    compare.score += (compare.asymmetry - compare.score) / compare.samples as f32;
+}
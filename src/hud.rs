@@ -0,0 +1,162 @@
+//! Always-visible text overlay with the state of the playing clip, instead of
+//! having to watch the console.
+
+use bevy::prelude::*;
+
+use crate::capture::capturing;
+use crate::clip_list::ClipBlend;
+use crate::inspector::BoneMeasure;
+use crate::locomotion::{FootSlideConfig, FootSliding};
+use crate::overlays::CenterOfMass;
+use crate::playback::{FrameStepConfig, SlowMotion};
+use crate::skeleton::SceneStats;
+use crate::ui::{UiDock, UiSettings};
+use crate::{AnimationsMetadata, CurrentAnimation, GizmosConfig};
+
+#[derive(Component)]
+struct Hud;
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_hud).add_systems(
+            Update,
+            (
+                update_hud,
+                capturing.pipe(hide_during_capture),
+                dock_hud.run_if(resource_changed::<UiSettings>()),
+            ),
+        );
+    }
+}
+
+fn spawn_hud(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 18.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            left: Val::Px(8.0),
+            padding: UiRect::all(Val::Px(6.0)),
+            ..default()
+        })
+        .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.5)),
+        Hud,
+    ));
+}
+
+fn update_hud(
+    current_animation: Res<CurrentAnimation>,
+    animation_meta: Res<AnimationsMetadata>,
+    frame_step: Res<FrameStepConfig>,
+    scene_stats: Res<SceneStats>,
+    foot_config: Res<FootSlideConfig>,
+    foot_sliding: Res<FootSliding>,
+    measure: Res<BoneMeasure>,
+    gizmos_config: Res<GizmosConfig>,
+    center_of_mass: Res<CenterOfMass>,
+    slow_motion: Res<SlowMotion>,
+    blend: Res<ClipBlend>,
+    clips: Res<Assets<AnimationClip>>,
+    players: Query<&AnimationPlayer>,
+    mut huds: Query<&mut Text, With<Hud>>,
+) {
+    let Some(player) = players.iter().next() else {
+        return;
+    };
+    let name = animation_meta
+        .0
+        .get(current_animation.0)
+        .map_or("?", |params| params.name.as_str());
+    let duration = clips
+        .get(player.animation_clip())
+        .map_or(0.0, |clip| clip.duration());
+    let time = player.seek_time();
+    let mut label = format!(
+        "{name}{}\n{time:.2}s / {duration:.2}s  frame {}\nspeed {:.2}x / {:.0}%\n{}",
+        if player.is_paused() { " (paused)" } else { "" },
+        frame_step.frame_at(time),
+        player.speed(),
+        player.speed() * 100.0,
+        format_args!(
+            "{} bones, {} meshes, {} vertices",
+            scene_stats.bones, scene_stats.meshes, scene_stats.vertices
+        )
+    );
+
+    if foot_config.enabled {
+        label += &format!(
+            "\nfoot contact below {:.2}m, sliding",
+            foot_config.contact_height
+        );
+        for (name, foot) in foot_config.feet.iter().zip(&foot_sliding.feet) {
+            label += &format!(" {name} {:.3}", foot.slide);
+        }
+    }
+
+    if let Some(target) = blend.target.and_then(|target| animation_meta.0.get(target)) {
+        label += &format!(
+            "\nblending {} at {:.2}, {} (')",
+            target.name,
+            blend.weight,
+            blend.mode.label()
+        );
+    }
+
+    if slow_motion.scale != 1.0 {
+        label += &format!("\nslow motion {}x (G)", slow_motion.scale);
+    }
+
+    if gizmos_config.show_scroll_lines {
+        label += &format!(
+            "\nreference lines 1m apart, scrolling {:.1} m/s",
+            gizmos_config.vel
+        );
+    }
+
+    if let Some(position) = center_of_mass
+        .position
+        .filter(|_| gizmos_config.show_center_of_mass)
+    {
+        label += &format!("\ncenter of mass x {:.3} z {:.3}", position.x, position.z);
+    }
+
+    if let (Some(from), Some(to), Some(distance)) = (&measure.from, &measure.to, measure.distance) {
+        label += &format!("\n{from} to {to}: {distance:.3}m");
+    }
+
+    for mut text in &mut huds {
+        if text.sections[0].value != label {
+            text.sections[0].value = label.clone();
+        }
+    }
+}
+
+/// Keeps the HUD out of contact sheets, sprite sheets and exported frames.
+fn hide_during_capture(In(capturing): In<bool>, mut huds: Query<&mut Visibility, With<Hud>>) {
+    let visibility = if capturing {
+        Visibility::Hidden
+    } else {
+        Visibility::Inherited
+    };
+    for mut hud_visibility in &mut huds {
+        hud_visibility.set_if_neq(visibility);
+    }
+}
+
+fn dock_hud(settings: Res<UiSettings>, mut huds: Query<&mut Style, With<Hud>>) {
+    for mut style in &mut huds {
+        (style.top, style.bottom) = match settings.dock {
+            UiDock::Top => (Val::Px(8.0), Val::Auto),
+            UiDock::Bottom => (Val::Auto, Val::Px(8.0)),
+        };
+    }
+}
@@ -0,0 +1,256 @@
+//! Two-bone IK chains drawn over, and optionally solved on top of, the clip.
+
+use bevy::animation::animation_player;
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+use bevy_inspector_egui::egui;
+
+use crate::skeleton::Skeleton;
+use crate::ui::keyboard_free;
+
+#[derive(Debug, Clone)]
+pub struct IkChain {
+    pub name: String,
+    /// Root, middle and end bone names, e.g. upper arm, forearm, hand.
+    pub bones: [String; 3],
+    /// World-space position the end bone reaches for.
+    pub target: Vec3,
+    pub show: bool,
+    pub solve: bool,
+}
+
+impl IkChain {
+    fn new(name: &str, bones: [&str; 3], target: Vec3) -> Self {
+        Self {
+            name: name.to_string(),
+            bones: bones.map(str::to_string),
+            target,
+            show: true,
+            solve: false,
+        }
+    }
+}
+
+#[derive(Resource, Debug)]
+pub struct IkConfig {
+    pub enabled: bool,
+    pub chains: Vec<IkChain>,
+}
+
+impl Default for IkConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            chains: vec![
+                IkChain::new(
+                    "left hand",
+                    ["LeftArm", "LeftForeArm", "LeftHand"],
+                    Vec3::new(0.3, 1.3, 0.3),
+                ),
+                IkChain::new(
+                    "right foot",
+                    ["RightUpLeg", "RightLeg", "RightFoot"],
+                    Vec3::new(0.2, 0.1, -0.15),
+                ),
+            ],
+        }
+    }
+}
+
+pub struct IkPlugin;
+
+impl Plugin for IkPlugin {
+    fn build(&self, app: &mut App) {
+        let enabled = |config: Res<IkConfig>| config.enabled;
+        app.init_resource::<IkConfig>()
+            .add_systems(
+                Update,
+                (
+                    toggle_ik.run_if(keyboard_free),
+                    (ik_panel, draw_ik_chains).run_if(enabled),
+                ),
+            )
+            .add_systems(
+                PostUpdate,
+                solve_ik_chains
+                    .run_if(enabled)
+                    .after(animation_player)
+                    .before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+fn toggle_ik(keyboard_input: Res<Input<KeyCode>>, mut config: ResMut<IkConfig>) {
+    if keyboard_input.just_pressed(KeyCode::K) {
+        config.enabled = !config.enabled;
+        println!("ik chains: {}", config.enabled);
+    }
+}
+
+fn ik_panel(mut contexts: EguiContexts, mut config: ResMut<IkConfig>) {
+    egui::Window::new("IK chains")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            for chain in &mut config.chains {
+                ui.label(chain.name.as_str());
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut chain.show, "show");
+                    ui.checkbox(&mut chain.solve, "solve");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("target");
+                    ui.add(egui::DragValue::new(&mut chain.target.x).speed(0.01));
+                    ui.add(egui::DragValue::new(&mut chain.target.y).speed(0.01));
+                    ui.add(egui::DragValue::new(&mut chain.target.z).speed(0.01));
+                });
+                ui.separator();
+            }
+        });
+}
+
+fn draw_ik_chains(
+    mut gizmos: Gizmos,
+    config: Res<IkConfig>,
+    skeleton: Res<Skeleton>,
+    global_transforms: Query<&GlobalTransform>,
+) {
+    for chain in config.chains.iter().filter(|chain| chain.show) {
+        gizmos.sphere(chain.target, Quat::IDENTITY, 0.04, Color::RED);
+
+        let Some(points) = chain
+            .bones
+            .iter()
+            .map(|name| {
+                let bone = skeleton.find(name)?;
+                Some(global_transforms.get(bone.entity).ok()?.translation())
+            })
+            .collect::<Option<Vec<_>>>()
+        else {
+            continue;
+        };
+        gizmos.linestrip(points.iter().copied(), Color::ORANGE);
+        gizmos.line(points[2], chain.target, Color::RED.with_a(0.4));
+    }
+}
+
+/// Clamped angle between two vectors.
+fn angle_between(a: Vec3, b: Vec3) -> f32 {
+    a.normalize_or_zero()
+        .dot(b.normalize_or_zero())
+        .clamp(-1.0, 1.0)
+        .acos()
+}
+
+/// Analytic two-bone IK. Takes the world transforms of the three joints and
+/// returns new local rotations for the root and middle joints that bring the
+/// end joint as close to `target` as the bone lengths allow.
+pub fn solve_two_bone(
+    a: &Transform,
+    b: &Transform,
+    c: &Transform,
+    a_local: Quat,
+    b_local: Quat,
+    target: Vec3,
+) -> (Quat, Quat) {
+    let (pa, pb, pc) = (a.translation, b.translation, c.translation);
+    let eps = 0.001;
+    let lab = (pb - pa).length();
+    let lcb = (pb - pc).length();
+    let lat = (target - pa).length().clamp(eps, lab + lcb - eps);
+
+    let ac_ab_0 = angle_between(pc - pa, pb - pa);
+    let ba_bc_0 = angle_between(pa - pb, pc - pb);
+    let ac_at_0 = angle_between(pc - pa, target - pa);
+
+    let ac_ab_1 = ((lcb * lcb - lab * lab - lat * lat) / (-2.0 * lab * lat))
+        .clamp(-1.0, 1.0)
+        .acos();
+    let ba_bc_1 = ((lat * lat - lab * lab - lcb * lcb) / (-2.0 * lab * lcb))
+        .clamp(-1.0, 1.0)
+        .acos();
+
+    let bend_axis = (pc - pa).cross(pb - pa).normalize_or_zero();
+    let swing_axis = (pc - pa).cross(target - pa).normalize_or_zero();
+
+    let r0 = Quat::from_axis_angle(a.rotation.inverse() * bend_axis, ac_ab_1 - ac_ab_0);
+    let r1 = Quat::from_axis_angle(b.rotation.inverse() * bend_axis, ba_bc_1 - ba_bc_0);
+    let r2 = Quat::from_axis_angle(a.rotation.inverse() * swing_axis, ac_at_0);
+
+    (
+        (a_local * (r0 * r2)).normalize(),
+        (b_local * r1).normalize(),
+    )
+}
+
+fn solve_ik_chains(
+    config: Res<IkConfig>,
+    skeleton: Res<Skeleton>,
+    parents: Query<&Parent>,
+    global_transforms: Query<&GlobalTransform>,
+    mut transforms: Query<&mut Transform>,
+) {
+    for chain in config.chains.iter().filter(|chain| chain.solve) {
+        let Some(entities) = chain
+            .bones
+            .iter()
+            .map(|name| skeleton.find(name).map(|bone| bone.entity))
+            .collect::<Option<Vec<_>>>()
+        else {
+            continue;
+        };
+        let Some(parent_world) = parents
+            .get(entities[0])
+            .ok()
+            .and_then(|parent| global_transforms.get(parent.get()).ok())
+            .map(|global| global.compute_transform())
+        else {
+            continue;
+        };
+
+        // Pose the chain from this frame's freshly sampled local transforms.
+        // The middle joint may sit below intermediate nodes, so walk up from
+        // each joint to the previous one.
+        let Ok(a_local) = transforms.get(entities[0]).copied() else {
+            continue;
+        };
+        let a = parent_world.mul_transform(a_local);
+        let Some(b_offset) = local_offset(entities[1], entities[0], &parents, &transforms) else {
+            continue;
+        };
+        let b = a.mul_transform(b_offset);
+        let Some(c_offset) = local_offset(entities[2], entities[1], &parents, &transforms) else {
+            continue;
+        };
+        let c = b.mul_transform(c_offset);
+        let Ok(b_local) = transforms.get(entities[1]).map(|t| t.rotation) else {
+            continue;
+        };
+
+        let (a_rotation, b_rotation) =
+            solve_two_bone(&a, &b, &c, a_local.rotation, b_local, chain.target);
+        if let Ok(mut transform) = transforms.get_mut(entities[0]) {
+            transform.rotation = a_rotation;
+        }
+        if let Ok(mut transform) = transforms.get_mut(entities[1]) {
+            transform.rotation = b_rotation;
+        }
+    }
+}
+
+/// Transform of `entity` relative to its ancestor `ancestor`, composed from the
+/// local transforms in between.
+fn local_offset(
+    entity: Entity,
+    ancestor: Entity,
+    parents: &Query<&Parent>,
+    transforms: &Query<&mut Transform>,
+) -> Option<Transform> {
+    let mut offset = *transforms.get(entity).ok()?;
+    let mut current = parents.get(entity).ok()?.get();
+    while current != ancestor {
+        offset = transforms.get(current).ok()?.mul_transform(offset);
+        current = parents.get(current).ok()?.get();
+    }
+    Some(offset)
+}
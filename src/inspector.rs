@@ -0,0 +1,230 @@
+//! Bone inspector panel: pick a bone and read back its transform, or pick two
+//! and measure the distance between them.
+
+use bevy::prelude::*;
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+use bevy_inspector_egui::egui;
+
+use crate::overlays::AngularVelocity;
+use crate::skeleton::Skeleton;
+use crate::ui::keyboard_free;
+use crate::{CurrentAnimation, GizmosConfig};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TransformSpace {
+    /// The bone's `Transform`, relative to its parent. This is what clips animate.
+    #[default]
+    Local,
+    /// The bone's `GlobalTransform`, i.e. the resulting world-space pose.
+    World,
+}
+
+impl TransformSpace {
+    pub fn label(self) -> &'static str {
+        match self {
+            TransformSpace::Local => "local",
+            TransformSpace::World => "world",
+        }
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct BoneInspector {
+    pub selected: Option<String>,
+    pub space: TransformSpace,
+    /// Highlight the selected bone and its children in the skeleton gizmo
+    /// and dim the rest. Cleared when the clip changes.
+    pub solo: bool,
+}
+
+impl BoneInspector {
+    /// The selected bone, if it's soloed.
+    pub fn soloed(&self) -> Option<&str> {
+        self.selected.as_deref().filter(|_| self.solo)
+    }
+}
+
+/// Two bones whose world-space distance is drawn and shown in the HUD, e.g.
+/// for stride length or hand spacing.
+#[derive(Resource, Debug, Default)]
+pub struct BoneMeasure {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    /// Distance between the two bones' origins this frame, when both are set.
+    pub distance: Option<f32>,
+}
+
+pub struct BoneInspectorPlugin;
+
+impl Plugin for BoneInspectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BoneInspector>()
+            .init_resource::<BoneMeasure>()
+            .add_systems(
+                Update,
+                (
+                    toggle_transform_space.run_if(keyboard_free),
+                    (bone_inspector_panel, measure_bones).chain(),
+                    clear_solo_on_clip_change.run_if(resource_changed::<CurrentAnimation>()),
+                ),
+            );
+    }
+}
+
+fn toggle_transform_space(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut inspector: ResMut<BoneInspector>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F2) {
+        inspector.space = match inspector.space {
+            TransformSpace::Local => TransformSpace::World,
+            TransformSpace::World => TransformSpace::Local,
+        };
+        println!("bone inspector space: {}", inspector.space.label());
+    }
+}
+
+fn bone_inspector_panel(
+    mut contexts: EguiContexts,
+    mut inspector: ResMut<BoneInspector>,
+    mut measure: ResMut<BoneMeasure>,
+    mut gizmos_config: ResMut<GizmosConfig>,
+    angular_velocity: Res<AngularVelocity>,
+    skeleton: Res<Skeleton>,
+    transforms: Query<(&Transform, &GlobalTransform)>,
+) {
+    let inspector = &mut *inspector;
+    egui::Window::new("Bone inspector")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            egui::ComboBox::from_label("bone")
+                .selected_text(inspector.selected.as_deref().unwrap_or("none"))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut inspector.selected, None, "none");
+                    for bone in &skeleton.bones {
+                        ui.selectable_value(
+                            &mut inspector.selected,
+                            Some(bone.name.clone()),
+                            bone.name.as_str(),
+                        );
+                    }
+                });
+
+            ui.checkbox(&mut inspector.solo, "solo in the skeleton gizmo");
+
+            let measure = &mut *measure;
+            ui.horizontal(|ui| {
+                ui.label("measure");
+                for (id, bone) in [("from", &mut measure.from), ("to", &mut measure.to)] {
+                    egui::ComboBox::from_id_source(id)
+                        .selected_text(bone.as_deref().unwrap_or("none"))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(bone, None, "none");
+                            for candidate in &skeleton.bones {
+                                ui.selectable_value(
+                                    bone,
+                                    Some(candidate.name.clone()),
+                                    candidate.name.as_str(),
+                                );
+                            }
+                        });
+                }
+            });
+            if let Some(distance) = measure.distance {
+                ui.label(format!("distance: {distance:.3}m"));
+            }
+
+            ui.horizontal(|ui| {
+                ui.label("space (F2):");
+                for space in [TransformSpace::Local, TransformSpace::World] {
+                    ui.selectable_value(&mut inspector.space, space, space.label());
+                }
+            });
+
+            let Some((transform, global)) = inspector
+                .selected
+                .as_deref()
+                .and_then(|name| skeleton.find(name))
+                .and_then(|bone| transforms.get(bone.entity).ok())
+            else {
+                return;
+            };
+            let transform = match inspector.space {
+                TransformSpace::Local => *transform,
+                TransformSpace::World => global.compute_transform(),
+            };
+            let (x, y, z) = transform.rotation.to_euler(EulerRot::XYZ);
+
+            ui.separator();
+            ui.label(format!("{} space", inspector.space.label()));
+            ui.label(format!("translation: {}", fmt_vec3(transform.translation)));
+            ui.label(format!(
+                "rotation (xyz deg): [{:.1}, {:.1}, {:.1}]",
+                x.to_degrees(),
+                y.to_degrees(),
+                z.to_degrees()
+            ));
+            ui.label(format!("scale: {}", fmt_vec3(transform.scale)));
+
+            ui.separator();
+            ui.checkbox(
+                &mut gizmos_config.show_angular_velocity,
+                "angular velocity gizmo",
+            );
+            if gizmos_config.show_angular_velocity {
+                ui.label(format!(
+                    "angular velocity: {:.1} deg/s about {}",
+                    angular_velocity.speed.to_degrees(),
+                    fmt_vec3(angular_velocity.axis)
+                ));
+            }
+        });
+}
+
+fn clear_solo_on_clip_change(mut inspector: ResMut<BoneInspector>) {
+    if inspector.solo {
+        inspector.solo = false;
+    }
+}
+
+/// Draws a line between the two measured bones and records its length.
+/// Bones missing from the skeleton, e.g. after switching models, are
+/// deselected.
+fn measure_bones(
+    mut gizmos: Gizmos,
+    mut measure: ResMut<BoneMeasure>,
+    skeleton: Res<Skeleton>,
+    global_transforms: Query<&GlobalTransform>,
+) {
+    let measure = &mut *measure;
+    if !skeleton.bones.is_empty() {
+        for bone in [&mut measure.from, &mut measure.to] {
+            if bone
+                .as_deref()
+                .is_some_and(|name| skeleton.find(name).is_none())
+            {
+                println!("measure: no bone named {}", bone.take().unwrap());
+            }
+        }
+    }
+
+    let position = |name: &Option<String>| {
+        let bone = skeleton.find(name.as_deref()?)?;
+        global_transforms
+            .get(bone.entity)
+            .ok()
+            .map(|global| global.translation())
+    };
+    let (Some(from), Some(to)) = (position(&measure.from), position(&measure.to)) else {
+        measure.distance = None;
+        return;
+    };
+    gizmos.line(from, to, Color::CYAN);
+    gizmos.sphere(from, Quat::IDENTITY, 0.02, Color::CYAN);
+    gizmos.sphere(to, Quat::IDENTITY, 0.02, Color::CYAN);
+    measure.distance = Some(from.distance(to));
+}
+
+pub fn fmt_vec3(v: Vec3) -> String {
+    format!("[{:.3}, {:.3}, {:.3}]", v.x, v.y, v.z)
+}
@@ -0,0 +1,135 @@
+//! Keys for the main playback controls, remappable through
+//! `assets/keybindings.ron` without recompiling.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub const KEYBINDINGS_PATH: &str = "assets/keybindings.ron";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Action {
+    PlayPause,
+    NextAnimation,
+    TPose,
+    SpeedUp,
+    SpeedDown,
+    Reverse,
+    /// Toggles playing each clip at the speed in its params.
+    ToggleParams,
+    SaveParams,
+    SeekForward,
+    SeekBackward,
+    FirstFrame,
+    LastFrame,
+    /// Steps one frame while paused.
+    StepForward,
+    StepBackward,
+    LineSpeedUp,
+    LineSpeedDown,
+    ResetLineSpeed,
+    /// Scrolls the reference lines vertically instead of horizontally.
+    ToggleLineAxis,
+    ToggleGrid,
+    ToggleScrollLines,
+    ToggleCenterOfMass,
+    ToggleSkeleton,
+    ToggleRootMotion,
+    ToggleBoneAxes,
+}
+
+/// Key bound to each action. A file only needs to list the actions it
+/// remaps, e.g. `{ SeekForward: PageDown, SeekBackward: PageUp }`; the rest
+/// keep their defaults.
+#[derive(Resource, Debug)]
+pub struct KeyBindings(pub HashMap<Action, KeyCode>);
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings(HashMap::from([
+            (Action::PlayPause, KeyCode::Space),
+            (Action::NextAnimation, KeyCode::Return),
+            (Action::TPose, KeyCode::T),
+            (Action::SpeedUp, KeyCode::A),
+            (Action::SpeedDown, KeyCode::Z),
+            (Action::Reverse, KeyCode::R),
+            (Action::ToggleParams, KeyCode::ControlLeft),
+            (Action::SaveParams, KeyCode::S),
+            (Action::SeekForward, KeyCode::Right),
+            (Action::SeekBackward, KeyCode::Left),
+            (Action::FirstFrame, KeyCode::Home),
+            (Action::LastFrame, KeyCode::End),
+            (Action::StepForward, KeyCode::Period),
+            (Action::StepBackward, KeyCode::Comma),
+            (Action::LineSpeedUp, KeyCode::Up),
+            (Action::LineSpeedDown, KeyCode::Down),
+            (Action::ResetLineSpeed, KeyCode::Key0),
+            (Action::ToggleLineAxis, KeyCode::Back),
+            (Action::ToggleGrid, KeyCode::BracketLeft),
+            (Action::ToggleScrollLines, KeyCode::BracketRight),
+            (Action::ToggleCenterOfMass, KeyCode::C),
+            (Action::ToggleSkeleton, KeyCode::B),
+            (Action::ToggleRootMotion, KeyCode::Backslash),
+            (Action::ToggleBoneAxes, KeyCode::O),
+        ]))
+    }
+}
+
+impl KeyBindings {
+    /// The defaults with `overrides` applied over them. Warns about keys
+    /// left bound to more than one action.
+    pub fn with_overrides(overrides: HashMap<Action, KeyCode>) -> Self {
+        let mut bindings = Self::default();
+        bindings.0.extend(overrides);
+        for (key, actions) in bindings.conflicts() {
+            println!("warning: {key:?} is bound to {actions:?}; all of them fire together");
+        }
+        bindings
+    }
+
+    /// Keys bound to more than one action, with those actions.
+    pub fn conflicts(&self) -> Vec<(KeyCode, Vec<Action>)> {
+        let mut by_key: BTreeMap<KeyCode, Vec<Action>> = BTreeMap::new();
+        for (&action, &key) in &self.0 {
+            by_key.entry(key).or_default().push(action);
+        }
+        by_key
+            .into_iter()
+            .filter(|(_, actions)| actions.len() > 1)
+            .map(|(key, mut actions)| {
+                actions.sort();
+                (key, actions)
+            })
+            .collect()
+    }
+
+    /// The defaults with the bindings in `path` applied over them.
+    pub fn from_file(path: &str) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        ron::from_str(&text)
+            .map(Self::with_overrides)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// The bindings in `path`, or the defaults if there's no such file or it
+    /// can't be read.
+    pub fn from_file_or_default(path: &str) -> Self {
+        match Self::from_file(path) {
+            Ok(bindings) => bindings,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Self::default(),
+            Err(e) => {
+                println!("warning: could not load {path}: {e}; using the default keys");
+                Self::default()
+            }
+        }
+    }
+
+    pub fn just_pressed(&self, action: Action, keyboard_input: &Input<KeyCode>) -> bool {
+        self.0
+            .get(&action)
+            .is_some_and(|&key| keyboard_input.just_pressed(key))
+    }
+}
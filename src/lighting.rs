@@ -0,0 +1,394 @@
+//! Lighting controls for judging how a pose reads.
+
+use std::f32::consts::PI;
+use std::fs;
+use std::io;
+
+use bevy::pbr::CascadeShadowConfigBuilder;
+use bevy::prelude::*;
+use bevy::render::primitives::Aabb;
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+use bevy_inspector_egui::egui;
+use serde::{Deserialize, Serialize};
+
+use crate::background::{BackgroundConfig, BackgroundMode};
+use crate::camera::mesh_bound_corners;
+use crate::ui::keyboard_free;
+use crate::GizmosConfig;
+
+/// Slowly sweeps the sun around the vertical axis to show the pose's form
+/// under changing light.
+#[derive(Resource, Debug)]
+pub struct LightOrbitConfig {
+    pub enabled: bool,
+    /// Radians per second.
+    pub speed: f32,
+}
+
+impl Default for LightOrbitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            speed: 0.3,
+        }
+    }
+}
+
+pub const SCENE_SETTINGS_PATH: &str = "assets/scene_settings.ron";
+
+/// Background, ambient light and sun direction, to match a game's look or
+/// dim a scene that's too bright. The defaults are the scene `setup` builds.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct SceneSettings {
+    pub background: Color,
+    pub ambient_brightness: f32,
+    /// Direction the sun shines from, in radians around the vertical axis.
+    pub sun_azimuth: f32,
+    /// Height of the sun above the horizon in radians.
+    pub sun_elevation: f32,
+}
+
+impl Default for SceneSettings {
+    fn default() -> Self {
+        Self {
+            background: ClearColor::default().0,
+            ambient_brightness: 1.0,
+            sun_azimuth: 1.0,
+            sun_elevation: PI / 4.0,
+        }
+    }
+}
+
+impl SceneSettings {
+    pub fn from_file(path: &str) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        ron::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(path, text)
+    }
+
+    pub fn light_rotation(&self) -> Quat {
+        Quat::from_euler(EulerRot::YXZ, self.sun_azimuth, -self.sun_elevation, 0.0)
+    }
+}
+
+pub struct LightingPlugin;
+
+impl Plugin for LightingPlugin {
+    fn build(&self, app: &mut App) {
+        let settings = match SceneSettings::from_file(SCENE_SETTINGS_PATH) {
+            Ok(settings) => settings,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => SceneSettings::default(),
+            Err(e) => {
+                println!("scene settings: could not load {SCENE_SETTINGS_PATH}: {e}");
+                SceneSettings::default()
+            }
+        };
+        app.init_resource::<LightOrbitConfig>()
+            .init_resource::<StudioModeConfig>()
+            .insert_resource(settings)
+            .add_systems(
+                Update,
+                (
+                    (scene_settings_panel, apply_scene_settings).chain(),
+                    draw_sun_direction
+                        .run_if(|config: Res<GizmosConfig>| config.show_sun_direction),
+                    (
+                        toggle_light_orbit.run_if(keyboard_free),
+                        orbit_light.run_if(|config: Res<LightOrbitConfig>| config.enabled),
+                    )
+                        .chain(),
+                    toggle_studio_mode.run_if(keyboard_free),
+                ),
+            );
+    }
+}
+
+fn scene_settings_panel(
+    mut contexts: EguiContexts,
+    mut settings: ResMut<SceneSettings>,
+    mut gizmos_config: ResMut<GizmosConfig>,
+) {
+    // Edit a copy so the settings only count as changed when a value does.
+    let mut edited = settings.clone();
+    egui::Window::new("Scene")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                let mut rgb = [
+                    edited.background.r(),
+                    edited.background.g(),
+                    edited.background.b(),
+                ];
+                ui.color_edit_button_rgb(&mut rgb);
+                edited.background = Color::rgb(rgb[0], rgb[1], rgb[2]);
+                ui.label("background");
+            });
+            ui.add(egui::Slider::new(&mut edited.ambient_brightness, 0.0..=2.0).text("ambient"));
+            ui.add(egui::Slider::new(&mut edited.sun_azimuth, -PI..=PI).text("sun azimuth"));
+            ui.add(
+                egui::Slider::new(&mut edited.sun_elevation, 0.0..=PI / 2.0).text("sun elevation"),
+            );
+            let mut show_sun_direction = gizmos_config.show_sun_direction;
+            ui.checkbox(&mut show_sun_direction, "show sun direction");
+            if show_sun_direction != gizmos_config.show_sun_direction {
+                gizmos_config.show_sun_direction = show_sun_direction;
+            }
+            ui.horizontal(|ui| {
+                if ui.button("reset").clicked() {
+                    edited = SceneSettings::default();
+                }
+                if ui.button("save").clicked() {
+                    match edited.save(SCENE_SETTINGS_PATH) {
+                        Ok(()) => println!("scene settings: saved to {SCENE_SETTINGS_PATH}"),
+                        Err(e) => {
+                            println!("scene settings: could not save {SCENE_SETTINGS_PATH}: {e}")
+                        }
+                    }
+                }
+            });
+        });
+    if edited != *settings {
+        *settings = edited;
+    }
+}
+
+/// Pushes edited settings into the scene. Values still at their defaults are
+/// left alone at startup, so a project file's background or studio mode
+/// isn't overridden until a setting is actually changed.
+fn apply_scene_settings(
+    settings: Res<SceneSettings>,
+    mut background: ResMut<BackgroundConfig>,
+    mut ambient: ResMut<AmbientLight>,
+    mut lights: Query<(&mut Transform, Ref<DirectionalLight>), Without<StudioRig>>,
+    mut previous: Local<Option<SceneSettings>>,
+) {
+    let before = previous.clone().unwrap_or_default();
+    if settings.background != before.background {
+        background.mode = BackgroundMode::Solid(settings.background);
+    }
+    if settings.ambient_brightness != before.ambient_brightness {
+        ambient.brightness = settings.ambient_brightness;
+    }
+    let defaults = SceneSettings::default();
+    let angles_changed = settings.sun_azimuth != before.sun_azimuth
+        || settings.sun_elevation != before.sun_elevation;
+    let custom_angles = settings.sun_azimuth != defaults.sun_azimuth
+        || settings.sun_elevation != defaults.sun_elevation;
+    for (mut transform, light) in &mut lights {
+        if angles_changed || (light.is_added() && custom_angles) {
+            transform.rotation = settings.light_rotation();
+        }
+    }
+    *previous = Some(settings.clone());
+}
+
+/// Arrow through the origin along the direction the sun shines, with a ball
+/// where it shines from. Follows the light itself, so it also shows the
+/// light orbit.
+fn draw_sun_direction(
+    mut gizmos: Gizmos,
+    lights: Query<&Transform, (With<DirectionalLight>, Without<StudioRig>)>,
+) {
+    for transform in &lights {
+        let direction = transform.forward();
+        let from = -direction * 2.0;
+        let tip = direction * 0.5;
+        gizmos.line(from, tip, Color::YELLOW);
+        gizmos.sphere(from, Quat::IDENTITY, 0.08, Color::YELLOW);
+        let side = direction.any_orthonormal_vector() * 0.08;
+        let back = tip - direction * 0.15;
+        gizmos.line(tip, back + side, Color::YELLOW);
+        gizmos.line(tip, back - side, Color::YELLOW);
+    }
+}
+
+fn toggle_light_orbit(keyboard_input: Res<Input<KeyCode>>, mut config: ResMut<LightOrbitConfig>) {
+    if keyboard_input.just_pressed(KeyCode::Q) {
+        config.enabled = !config.enabled;
+        println!("light orbit: {}", config.enabled);
+    }
+}
+
+fn orbit_light(
+    time: Res<Time>,
+    config: Res<LightOrbitConfig>,
+    mut lights: Query<&mut Transform, With<DirectionalLight>>,
+) {
+    for mut transform in &mut lights {
+        transform.rotate_y(config.speed * time.delta_seconds());
+    }
+}
+
+/// One-key presentation setup: neutral background, key/fill/rim lights, a
+/// shadow-catching ground plane and the camera framed on the character.
+/// Whatever it overrides is restored when it is switched off.
+#[derive(Resource, Debug)]
+pub struct StudioModeConfig {
+    pub enabled: bool,
+    pub background: Color,
+    pub ground: Color,
+    /// Illuminance of the key light; fill and rim are fractions of it.
+    pub key_illuminance: f32,
+}
+
+impl Default for StudioModeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            background: Color::rgb(0.45, 0.45, 0.45),
+            ground: Color::rgb(0.6, 0.6, 0.6),
+            key_illuminance: 20_000.0,
+        }
+    }
+}
+
+#[derive(Component)]
+struct StudioRig;
+
+/// What studio mode replaced, to put back when it is switched off.
+#[derive(Resource)]
+struct StudioStash {
+    ambient: AmbientLight,
+    background: BackgroundMode,
+    cameras: Vec<(Entity, Transform, Projection)>,
+    lights: Vec<(Entity, Visibility)>,
+}
+
+fn toggle_studio_mode(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut config: ResMut<StudioModeConfig>,
+    stash: Option<Res<StudioStash>>,
+    mut ambient: ResMut<AmbientLight>,
+    mut background: ResMut<BackgroundConfig>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    bounds: Query<(&Aabb, &GlobalTransform), With<Handle<Mesh>>>,
+    mut cameras: Query<(Entity, &mut Transform, &mut Projection), With<Camera3d>>,
+    mut lights: Query<(Entity, &mut Visibility), (With<DirectionalLight>, Without<StudioRig>)>,
+    rig: Query<Entity, With<StudioRig>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F1) {
+        return;
+    }
+    config.enabled = !config.enabled;
+    println!("studio mode: {}", config.enabled);
+
+    if !config.enabled {
+        let Some(stash) = stash else {
+            return;
+        };
+        *ambient = stash.ambient.clone();
+        background.mode = stash.background.clone();
+        for (entity, transform, projection) in &stash.cameras {
+            if let Ok((_, mut camera_transform, mut camera_projection)) = cameras.get_mut(*entity) {
+                *camera_transform = *transform;
+                *camera_projection = projection.clone();
+            }
+        }
+        for (entity, visibility) in &stash.lights {
+            if let Ok((_, mut light_visibility)) = lights.get_mut(*entity) {
+                *light_visibility = *visibility;
+            }
+        }
+        for entity in &rig {
+            commands.entity(entity).despawn_recursive();
+        }
+        commands.remove_resource::<StudioStash>();
+        return;
+    }
+
+    commands.insert_resource(StudioStash {
+        ambient: ambient.clone(),
+        background: background.mode.clone(),
+        cameras: cameras
+            .iter()
+            .map(|(entity, transform, projection)| (entity, *transform, projection.clone()))
+            .collect(),
+        lights: lights
+            .iter()
+            .map(|(entity, visibility)| (entity, *visibility))
+            .collect(),
+    });
+    for (_, mut visibility) in &mut lights {
+        *visibility = Visibility::Hidden;
+    }
+    background.mode = BackgroundMode::Solid(config.background);
+    *ambient = AmbientLight {
+        color: Color::WHITE,
+        brightness: 0.3,
+    };
+
+    let corners = mesh_bound_corners(&bounds);
+    let (min, max) = corners.iter().fold(
+        (Vec3::splat(f32::INFINITY), Vec3::splat(f32::NEG_INFINITY)),
+        |(min, max), &corner| (min.min(corner), max.max(corner)),
+    );
+    let (center, size) = if corners.is_empty() {
+        (Vec3::Y, Vec3::splat(2.0))
+    } else {
+        ((min + max) / 2.0, max - min)
+    };
+    let floor = center.y - size.y / 2.0;
+
+    for (_, mut transform, mut projection) in &mut cameras {
+        let direction = (transform.translation - center).normalize_or_zero();
+        let direction = if direction == Vec3::ZERO {
+            Vec3::Z
+        } else {
+            direction
+        };
+        *transform =
+            Transform::from_translation(center + direction * 50.0).looking_at(center, Vec3::Y);
+        if let Projection::Orthographic(ortho) = &mut *projection {
+            // With the fixed vertical scaling from `setup` the view is
+            // `2 * scale` tall; leave a margin around the character.
+            ortho.scale = size.max_element() * 0.65;
+        }
+    }
+
+    let rig_lights = [
+        (Vec3::new(1.0, 1.0, 1.0), 1.0, true),
+        (Vec3::new(-1.0, 0.5, 0.8), 0.4, false),
+        (Vec3::new(0.0, 1.0, -1.0), 0.6, false),
+    ];
+    for (from, fraction, shadows) in rig_lights {
+        commands.spawn((
+            DirectionalLightBundle {
+                directional_light: DirectionalLight {
+                    illuminance: config.key_illuminance * fraction,
+                    shadows_enabled: shadows,
+                    ..default()
+                },
+                cascade_shadow_config: CascadeShadowConfigBuilder {
+                    first_cascade_far_bound: 10.0,
+                    maximum_distance: 100.0,
+                    ..default()
+                }
+                .into(),
+                transform: Transform::from_translation(center + from).looking_at(center, Vec3::Y),
+                ..default()
+            },
+            StudioRig,
+        ));
+    }
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(shape::Plane::from_size(size.max_element() * 3.0).into()),
+            material: materials.add(StandardMaterial {
+                base_color: config.ground,
+                perceptual_roughness: 1.0,
+                ..default()
+            }),
+            transform: Transform::from_xyz(center.x, floor, center.z),
+            ..default()
+        },
+        StudioRig,
+    ));
+}
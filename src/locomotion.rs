@@ -0,0 +1,246 @@
+//! Quality readouts for locomotion clips.
+
+use bevy::prelude::*;
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+use bevy_inspector_egui::egui;
+
+use crate::skeleton::Skeleton;
+use crate::ui::keyboard_free;
+use crate::{Animations, AnimationsMetadata, ModelRoot};
+
+/// Measures how far planted feet slide over the ground.
+///
+/// A foot is in contact while its bone is below `contact_height`, and the
+/// contact is sliding while it also moves faster than `contact_speed` over
+/// the ground. For clips with
+/// an authored velocity the ground is assumed to scroll under the in-place
+/// character at that velocity, so only drift relative to the moving ground
+/// counts as sliding.
+#[derive(Resource, Debug)]
+pub struct FootSlideConfig {
+    pub enabled: bool,
+    pub feet: Vec<String>,
+    /// World-space height below which a foot counts as planted.
+    pub contact_height: f32,
+    /// Horizontal speed over the ground, in meters per second, above which a
+    /// planted foot is sliding.
+    pub contact_speed: f32,
+    /// Slides longer than this are drawn as a problem.
+    pub tolerance: f32,
+}
+
+impl Default for FootSlideConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            feet: vec!["LeftToeBase".to_string(), "RightToeBase".to_string()],
+            contact_height: 0.08,
+            contact_speed: 0.1,
+            tolerance: 0.02,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct FootSlide {
+    /// Where the foot was planted, moved along with the scrolling ground.
+    pub anchor: Option<Vec3>,
+    /// Horizontal distance from the anchor during the current contact.
+    pub slide: f32,
+    /// Horizontal speed over the ground this frame.
+    pub speed: f32,
+    /// Position last frame, for the speed.
+    previous: Option<Vec3>,
+    /// Largest slide of the current contact.
+    pub step_max: f32,
+    /// Largest slide of the previous contact.
+    pub last_step: f32,
+    /// Largest slide of any contact since the clip started.
+    pub max: f32,
+}
+
+/// Per-foot slide measurements, in `FootSlideConfig::feet` order.
+#[derive(Resource, Debug, Default)]
+pub struct FootSliding {
+    clip: Option<AssetId<AnimationClip>>,
+    pub feet: Vec<FootSlide>,
+}
+
+pub struct LocomotionPlugin;
+
+impl Plugin for LocomotionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FootSlideConfig>()
+            .init_resource::<FootSliding>()
+            .add_systems(
+                Update,
+                (
+                    toggle_foot_sliding.run_if(keyboard_free),
+                    (measure_foot_sliding, draw_foot_sliding, foot_sliding_panel)
+                        .chain()
+                        .run_if(|config: Res<FootSlideConfig>| config.enabled)
+                        .run_if(resource_exists::<Animations>()),
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn toggle_foot_sliding(keyboard_input: Res<Input<KeyCode>>, mut config: ResMut<FootSlideConfig>) {
+    if keyboard_input.just_pressed(KeyCode::Slash) {
+        config.enabled = !config.enabled;
+        println!("foot sliding readout: {}", config.enabled);
+    }
+}
+
+fn measure_foot_sliding(
+    time: Res<Time>,
+    config: Res<FootSlideConfig>,
+    mut sliding: ResMut<FootSliding>,
+    skeleton: Res<Skeleton>,
+    animations: Res<Animations>,
+    animation_meta: Res<AnimationsMetadata>,
+    players: Query<&AnimationPlayer>,
+    roots: Query<&GlobalTransform, With<ModelRoot>>,
+    global_transforms: Query<&GlobalTransform>,
+) {
+    let Some(player) = players.iter().next() else {
+        return;
+    };
+    let clip = player.animation_clip().id();
+    if sliding.clip != Some(clip) || sliding.feet.len() != config.feet.len() {
+        *sliding = FootSliding {
+            clip: Some(clip),
+            feet: vec![FootSlide::default(); config.feet.len()],
+        };
+    }
+
+    let authored_velocity = animations
+        .0
+        .iter()
+        .position(|handle| handle.id() == clip)
+        .and_then(|index| animation_meta.0.get(index))
+        .and_then(|params| params.authored_velocity)
+        .unwrap_or(0.0);
+    let forward = roots
+        .iter()
+        .next()
+        .map(|root| root.compute_transform().rotation * Vec3::Z)
+        .unwrap_or(Vec3::Z);
+    let ground_step = if player.is_paused() {
+        Vec3::ZERO
+    } else {
+        -forward * authored_velocity * player.speed() * time.delta_seconds()
+    };
+
+    for (name, foot) in config.feet.iter().zip(&mut sliding.feet) {
+        let Some(position) = skeleton
+            .find(name)
+            .and_then(|bone| global_transforms.get(bone.entity).ok())
+            .map(|global| global.translation())
+        else {
+            continue;
+        };
+
+        let ground_drift = foot
+            .previous
+            .replace(position)
+            .map_or(Vec3::ZERO, |previous| position - previous - ground_step);
+        foot.speed = Vec2::new(ground_drift.x, ground_drift.z).length()
+            / time.delta_seconds().max(f32::EPSILON);
+
+        if position.y > config.contact_height {
+            if foot.anchor.take().is_some() {
+                foot.last_step = foot.step_max;
+            }
+            foot.slide = 0.0;
+            foot.step_max = 0.0;
+            continue;
+        }
+
+        let anchor = foot.anchor.get_or_insert(position);
+        *anchor += ground_step;
+        let drift = position - *anchor;
+        foot.slide = Vec2::new(drift.x, drift.z).length();
+        foot.step_max = foot.step_max.max(foot.slide);
+        foot.max = foot.max.max(foot.slide);
+    }
+}
+
+fn draw_foot_sliding(
+    mut gizmos: Gizmos,
+    config: Res<FootSlideConfig>,
+    sliding: Res<FootSliding>,
+    skeleton: Res<Skeleton>,
+    global_transforms: Query<&GlobalTransform>,
+) {
+    for (name, foot) in config.feet.iter().zip(&sliding.feet) {
+        let Some(anchor) = foot.anchor else {
+            continue;
+        };
+        let Some(position) = skeleton
+            .find(name)
+            .and_then(|bone| global_transforms.get(bone.entity).ok())
+            .map(|global| global.translation())
+        else {
+            continue;
+        };
+        let color = if foot.slide > config.tolerance {
+            Color::RED
+        } else {
+            Color::GREEN
+        };
+        let ground = Vec3::new(position.x, anchor.y, position.z);
+        gizmos.circle(anchor, Vec3::Y, 0.03, color);
+        gizmos.line(anchor, ground, color);
+
+        // Contact marker on the ground, filled in with rings: green while
+        // the foot holds still, orange while it slides.
+        let marker = if foot.speed > config.contact_speed {
+            Color::ORANGE
+        } else {
+            Color::GREEN
+        };
+        let center = Vec3::new(position.x, 0.0, position.z);
+        for ring in 1..=5 {
+            gizmos.circle(center, Vec3::Y, 0.02 * ring as f32, marker.with_a(0.6));
+        }
+    }
+}
+
+fn foot_sliding_panel(
+    mut contexts: EguiContexts,
+    mut config: ResMut<FootSlideConfig>,
+    sliding: Res<FootSliding>,
+    skeleton: Res<Skeleton>,
+) {
+    let config = &mut *config;
+    egui::Window::new("Foot sliding")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.add(egui::Slider::new(&mut config.contact_height, 0.0..=0.5).text("contact height"));
+            ui.add(egui::Slider::new(&mut config.contact_speed, 0.0..=1.0).text("contact speed"));
+            egui::Grid::new("foot_sliding").show(ui, |ui| {
+                ui.label("foot");
+                ui.label("speed");
+                ui.label("now");
+                ui.label("last step");
+                ui.label("max");
+                ui.end_row();
+                for (index, (name, foot)) in config.feet.iter_mut().zip(&sliding.feet).enumerate() {
+                    egui::ComboBox::from_id_source(("foot", index))
+                        .selected_text(name.as_str())
+                        .show_ui(ui, |ui| {
+                            for bone in &skeleton.bones {
+                                ui.selectable_value(name, bone.name.clone(), bone.name.as_str());
+                            }
+                        });
+                    ui.label(format!("{:.2}", foot.speed));
+                    ui.label(format!("{:.3}", foot.slide));
+                    ui.label(format!("{:.3}", foot.last_step));
+                    ui.label(format!("{:.3}", foot.max));
+                    ui.end_row();
+                }
+            });
+        });
+}
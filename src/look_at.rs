@@ -0,0 +1,205 @@
+//! Procedural head tracking layered on top of the playing clip, to preview
+//! how an additive look-at combines with idle animations.
+
+use bevy::animation::animation_player;
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+use bevy::window::PrimaryWindow;
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+use bevy_inspector_egui::egui;
+
+use crate::sampling::pose_world;
+use crate::skeleton::Skeleton;
+use crate::ui::keyboard_free;
+
+#[derive(Resource, Debug)]
+pub struct LookAtConfig {
+    pub enabled: bool,
+    /// World-space point to look at, unless following the cursor.
+    pub target: Vec3,
+    pub follow_cursor: bool,
+    /// Turn the spine as well as the neck and head.
+    pub include_spine: bool,
+    /// Bones that share the turn, from the spine up, with their share of it.
+    pub bones: Vec<(String, f32)>,
+    /// Spine bones, which only turn when `include_spine` is set.
+    pub spine_bones: usize,
+    /// Axis the head looks along, in the head bone's local space.
+    pub forward: Vec3,
+    /// The turn is clamped to this many degrees away from the clip's pose.
+    pub max_degrees: f32,
+}
+
+impl Default for LookAtConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            target: Vec3::new(1.0, 1.6, 1.0),
+            follow_cursor: false,
+            include_spine: false,
+            bones: vec![
+                ("Spine2".to_string(), 0.2),
+                ("Neck".to_string(), 0.3),
+                ("Head".to_string(), 0.5),
+            ],
+            spine_bones: 1,
+            forward: Vec3::Z,
+            max_degrees: 70.0,
+        }
+    }
+}
+
+pub struct LookAtPlugin;
+
+impl Plugin for LookAtPlugin {
+    fn build(&self, app: &mut App) {
+        let enabled = |config: Res<LookAtConfig>| config.enabled;
+        app.init_resource::<LookAtConfig>()
+            .add_systems(
+                Update,
+                (
+                    toggle_look_at.run_if(keyboard_free),
+                    (look_at_panel, target_cursor, draw_look_at_target)
+                        .chain()
+                        .run_if(enabled),
+                ),
+            )
+            .add_systems(
+                PostUpdate,
+                apply_look_at
+                    .run_if(enabled)
+                    .after(animation_player)
+                    .before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+fn toggle_look_at(keyboard_input: Res<Input<KeyCode>>, mut config: ResMut<LookAtConfig>) {
+    if keyboard_input.just_pressed(KeyCode::H) {
+        config.enabled = !config.enabled;
+        println!("head look-at: {}", config.enabled);
+    }
+}
+
+fn look_at_panel(mut contexts: EguiContexts, mut config: ResMut<LookAtConfig>) {
+    egui::Window::new("Look-at")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.checkbox(&mut config.follow_cursor, "follow cursor");
+            ui.add_enabled_ui(!config.follow_cursor, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("target");
+                    ui.add(egui::DragValue::new(&mut config.target.x).speed(0.01));
+                    ui.add(egui::DragValue::new(&mut config.target.y).speed(0.01));
+                    ui.add(egui::DragValue::new(&mut config.target.z).speed(0.01));
+                });
+            });
+            ui.checkbox(&mut config.include_spine, "turn the spine too");
+            ui.add(egui::Slider::new(&mut config.max_degrees, 0.0..=120.0).text("limit (deg)"));
+        });
+}
+
+/// Puts the target where the cursor meets a plane through the old target,
+/// facing the camera.
+fn target_cursor(
+    mut config: ResMut<LookAtConfig>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
+) {
+    if !config.follow_cursor {
+        return;
+    }
+    let Some(cursor) = windows
+        .iter()
+        .next()
+        .and_then(|window| window.cursor_position())
+    else {
+        return;
+    };
+    let Some((camera, camera_transform)) = cameras.iter().find(|(camera, _)| camera.is_active)
+    else {
+        return;
+    };
+    let Some(ray) = camera.viewport_to_world(camera_transform, cursor) else {
+        return;
+    };
+    if let Some(distance) = ray.intersect_plane(config.target, camera_transform.back()) {
+        config.target = ray.get_point(distance);
+    }
+}
+
+fn draw_look_at_target(
+    mut gizmos: Gizmos,
+    config: Res<LookAtConfig>,
+    skeleton: Res<Skeleton>,
+    global_transforms: Query<&GlobalTransform>,
+) {
+    gizmos.sphere(config.target, Quat::IDENTITY, 0.05, Color::CYAN);
+    let head = config
+        .bones
+        .last()
+        .and_then(|(name, _)| skeleton.find(name));
+    if let Some(global) = head.and_then(|bone| global_transforms.get(bone.entity).ok()) {
+        gizmos.line(global.translation(), config.target, Color::CYAN.with_a(0.4));
+    }
+}
+
+/// Turns the head towards the target, spreading the turn over the neck (and
+/// spine) by their shares. Every bone turns about the same world axis, so
+/// each one's share can be applied independently of the bones above it.
+fn apply_look_at(
+    config: Res<LookAtConfig>,
+    skeleton: Res<Skeleton>,
+    parents: Query<&Parent>,
+    global_transforms: Query<&GlobalTransform>,
+    mut transforms: Query<&mut Transform>,
+) {
+    let pose: Vec<Transform> = skeleton
+        .bones
+        .iter()
+        .map(|bone| transforms.get(bone.entity).copied().unwrap_or(bone.rest))
+        .collect();
+    let world = pose_world(&skeleton, &pose, &parents, &global_transforms);
+
+    let skip = if config.include_spine {
+        0
+    } else {
+        config.spine_bones
+    };
+    let Some(turning) = config
+        .bones
+        .iter()
+        .skip(skip)
+        .map(|(name, share)| Some((skeleton.index_of(name)?, *share)))
+        .collect::<Option<Vec<_>>>()
+    else {
+        return;
+    };
+    let Some(&(head, _)) = turning.last() else {
+        return;
+    };
+    let total: f32 = turning.iter().map(|(_, share)| share).sum();
+    if total <= 0.0 {
+        return;
+    }
+
+    let forward = world[head].rotation * config.forward;
+    let to_target = (config.target - world[head].translation).normalize_or_zero();
+    let axis = forward.cross(to_target).normalize_or_zero();
+    if axis == Vec3::ZERO {
+        return;
+    }
+    let angle = forward
+        .angle_between(to_target)
+        .min(config.max_degrees.to_radians());
+
+    for (index, share) in turning {
+        let Ok(mut transform) = transforms.get_mut(skeleton.bones[index].entity) else {
+            continue;
+        };
+        let turn = Quat::from_axis_angle(axis, angle * share / total);
+        let parent_rotation = world[index].rotation * pose[index].rotation.inverse();
+        transform.rotation =
+            (parent_rotation.inverse() * turn * parent_rotation * pose[index].rotation).normalize();
+    }
+}
@@ -1,17 +1,87 @@
 //! Plays animations from a skinned glTF.
+//!
+//! `--model <file>` and `--animations <file>` swap in other glTF files from
+//! the asset folder; the animation file replaces the file part of every
+//! clip path, keeping the `#AnimationN` labels. `--animations-dir <dir>`
+//! lists every clip of every glTF in a folder instead (see
+//! `scan::AnimationDirectoryScan`), and `--gltf <file>` plays a character
+//! with the clips bundled in its own file. `--export <dir>` screenshots
+//! every animation and exits (see `capture::BatchExport`). `--frames-dir` and
+//! `--frames-fps` set where and how densely F12 writes a PNG sequence, and
+//! `--dock top|bottom` moves the HUD and the timeline (see `ui::UiDock`).
+//! `--render-size 1920x1080` renders the scene at a fixed size (see
+//! `camera::RenderResolution`).
 
 use std::f32::consts::PI;
+use std::fs;
+use std::io;
+use std::path::Path;
 use std::time::Duration;
 
+use bevy::animation::RepeatAnimation;
 use bevy::pbr::CascadeShadowConfigBuilder;
 use bevy::prelude::*;
 use bevy::render::camera::ScalingMode;
-
-#[derive(Default, Debug)]
+use bevy::render::settings::{WgpuFeatures, WgpuSettings};
+use bevy::render::RenderPlugin;
+use serde::{Deserialize, Serialize};
+
+use keybindings::{Action, KeyBindings, KEYBINDINGS_PATH};
+
+mod audio;
+mod background;
+mod batch;
+#[cfg(feature = "bvh")]
+mod bvh;
+mod camera;
+mod capture;
+mod clip_list;
+mod compare;
+mod diagnostics;
+mod dope_sheet;
+mod events;
+mod export;
+mod ghosts;
+mod hud;
+mod ik;
+mod inspector;
+mod keybindings;
+mod lighting;
+mod locomotion;
+mod look_at;
+mod overlays;
+mod parts;
+mod path;
+mod playback;
+mod poses;
+mod project;
+mod reload;
+mod retarget;
+mod sampling;
+mod scan;
+mod secondary;
+mod skeleton;
+mod ui;
+
+#[derive(Serialize, Deserialize, Default, Debug)]
 pub struct AnimationParams {
     pub path: String,
     pub name: String,
+    #[serde(default = "default_playback_speed")]
     pub playback_speed: f32,
+    #[serde(default)]
+    pub events: Vec<events::AnimationEvent>,
+    /// Audio track played in sync with the clip, e.g. `"audio/walk.ogg"`.
+    #[serde(default)]
+    pub audio: Option<String>,
+    /// Ground speed the clip was animated for, in meters per second, for
+    /// locomotion clips.
+    #[serde(default)]
+    pub authored_velocity: Option<f32>,
+}
+
+fn default_playback_speed() -> f32 {
+    1.0
 }
 
 impl AnimationParams {
@@ -19,15 +89,72 @@ impl AnimationParams {
         Self {
             path: path.to_string(),
             name: name.to_string(),
-            playback_speed: 1.0,
+            playback_speed: default_playback_speed(),
+            events: Vec::new(),
+            audio: None,
+            authored_velocity: None,
         }
     }
+
+    pub fn with_event(mut self, time: f32, kind: events::EventKind) -> Self {
+        self.events.push(events::AnimationEvent { time, kind });
+        self
+    }
 }
 
+pub const ANIMATIONS_PATH: &str = "assets/animations.ron";
+
 #[derive(Resource, Default, Debug)]
 pub struct AnimationsMetadata(pub Vec<AnimationParams>);
 
+/// File `AnimationsMetadata` was read from, which Shift+S saves it back to.
+/// `None` when the list came from somewhere it shouldn't be written over:
+/// a project file, `--animations`, a folder scan or `--gltf`.
+#[derive(Resource, Default, Debug)]
+pub struct AnimationsFile(pub Option<String>);
+
 impl AnimationsMetadata {
+    /// Reads a RON list of `AnimationParams`.
+    pub fn from_file(path: &str) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        ron::from_str(&text)
+            .map(AnimationsMetadata)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Writes the list back out in the format `from_file` reads. Clips
+    /// imported from BVH files are left out; they're added again at startup.
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let params: Vec<_> = self
+            .0
+            .iter()
+            .filter(|params| !params.path.to_lowercase().ends_with(".bvh"))
+            .collect();
+        let text = ron::ser::to_string_pretty(&params, ron::ser::PrettyConfig::default())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(path, text)
+    }
+
+    /// The list in `path`, or the built-in one if it can't be read, along
+    /// with the file it may be saved back to. A missing file is created on
+    /// save; one that failed to parse or lists no clips is never overwritten.
+    pub fn from_file_or_default(path: &str) -> (Self, AnimationsFile) {
+        match Self::from_file(path) {
+            Ok(list) if list.0.is_empty() => {
+                println!("warning: {path} lists no animations; using the built-in animation list");
+                (Self::new(), AnimationsFile(None))
+            }
+            Ok(list) => (list, AnimationsFile(Some(path.to_string()))),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                (Self::new(), AnimationsFile(Some(path.to_string())))
+            }
+            Err(e) => {
+                println!("warning: could not load {path}: {e}; using the built-in animation list");
+                (Self::new(), AnimationsFile(None))
+            }
+        }
+    }
+
     pub fn new() -> Self {
         AnimationsMetadata(vec![
             AnimationParams::new("all_animations_6.glb#Animation0", "TPose"),
@@ -42,9 +169,11 @@ impl AnimationsMetadata {
             AnimationParams::new("all_animations_6.glb#Animation9", "DashPose"),
             AnimationParams::new("all_animations_6.glb#Animation10", "RunFast"),
             AnimationParams::new("all_animations_6.glb#Animation11", "RunJog"),
-            AnimationParams::new("all_animations_6.glb#Animation12", "Walk"),
+            AnimationParams::new("all_animations_6.glb#Animation12", "Walk")
+                .with_event(0.0, events::EventKind::PlaySound("footstep".to_string())),
             AnimationParams::new("all_animations_6.glb#Animation13", "WalkStride"),
-            AnimationParams::new("all_animations_6.glb#Animation14", "JumpAscent"),
+            AnimationParams::new("all_animations_6.glb#Animation14", "JumpAscent")
+                .with_event(0.0, events::EventKind::SpawnEffect("dust".to_string())),
             AnimationParams::new("all_animations_6.glb#Animation15", "LadderHandsWide"),
             AnimationParams::new("all_animations_6.glb#Animation16", "LadderHandsMedium"),
             AnimationParams::new("all_animations_6.glb#Animation17", "WallSlide"),
@@ -52,27 +181,170 @@ impl AnimationsMetadata {
     }
 }
 
+/// Value following `flag` on the command line, e.g. `--project my.project.ron`.
+pub fn arg_value(flag: &str) -> Option<String> {
+    let mut args = std::env::args().skip_while(|arg| arg != flag);
+    args.next()?;
+    args.next()
+}
+
+/// Asset path given after `flag` on the command line, if its file exists
+/// under the asset folder.
+fn asset_arg(flag: &str) -> Option<String> {
+    let path = arg_value(flag)?;
+    let file = path.split('#').next().unwrap_or_default();
+    if Path::new("assets").join(file).is_file() {
+        Some(path)
+    } else {
+        println!("error: {flag} {path}: assets/{file} does not exist, keeping the default");
+        None
+    }
+}
+
 fn main() {
-    App::new()
-        .add_plugins((DefaultPlugins.set(AssetPlugin { ..default() }),))
+    if let Some(validation) = batch::BatchValidation::from_args() {
+        validation.run();
+        return;
+    }
+    let project =
+        arg_value("--project").and_then(|path| match project::ProjectFile::from_file(&path) {
+            Ok(project) => Some(project),
+            Err(e) => {
+                println!("could not load project {path}: {e}");
+                None
+            }
+        });
+
+    let (animation_meta, animations_file) =
+        AnimationsMetadata::from_file_or_default(ANIMATIONS_PATH);
+
+    let mut app = App::new();
+    app.add_plugins((DefaultPlugins
+        .set(AssetPlugin {
+            // Reload the model and clips when they're re-exported.
+            watch_for_changes_override: Some(true),
+            ..default()
+        })
+        .set(RenderPlugin {
+            // Line polygon mode for the wireframe view (see `parts`).
+            render_creation: WgpuSettings {
+                features: WgpuFeatures::POLYGON_MODE_LINE,
+                ..default()
+            }
+            .into(),
+        }),))
         .insert_resource(AmbientLight {
             color: Color::WHITE,
             brightness: 1.0,
         })
-        .insert_resource(AnimationsMetadata::new())
+        .insert_resource(animation_meta)
+        .insert_resource(animations_file)
+        .insert_resource(KeyBindings::from_file_or_default(KEYBINDINGS_PATH))
+        .init_resource::<GizmosConfig>()
+        .init_resource::<ModelConfig>()
+        .init_resource::<CurrentAnimation>()
+        .add_plugins((
+            capture::ContactSheetPlugin,
+            diagnostics::ClipDiagnosticsPlugin,
+            ui::UiPlugin,
+            skeleton::SkeletonPlugin,
+            secondary::SecondaryMotionPlugin,
+            inspector::BoneInspectorPlugin,
+            poses::PoseLibraryPlugin,
+            overlays::OverlaysPlugin,
+            playback::PlaybackPlugin,
+            retarget::RetargetPlugin,
+            camera::CameraToolsPlugin,
+            events::AnimationEventsPlugin,
+            ik::IkPlugin,
+            audio::AudioSyncPlugin,
+            ghosts::GhostsPlugin,
+        ))
+        .add_plugins((
+            export::ObjExportPlugin,
+            background::BackgroundPlugin,
+            lighting::LightingPlugin,
+            parts::MeshPartsPlugin,
+            locomotion::LocomotionPlugin,
+            path::MotionPathPlugin,
+            look_at::LookAtPlugin,
+            dope_sheet::DopeSheetPlugin,
+            reload::ModelReloadPlugin,
+            clip_list::ClipListPlugin,
+            hud::HudPlugin,
+            compare::SideBySidePlugin,
+            scan::AnimationDirectoryPlugin,
+        ))
         // .add_systems(Startup, setup)
         .add_systems(
             Update,
             (
                 setup.run_if(
                     resource_exists::<AnimationsMetadata>()
-                        .and_then(not(resource_exists::<AnimationsLoadedMarker>())),
+                        .and_then(not(resource_exists::<AnimationsLoadedMarker>()))
+                        .and_then(not(resource_exists::<scan::AnimationDirectoryScan>())),
                 ),
                 setup_scene_once_loaded.run_if(resource_exists::<Animations>()),
-                keyboard_animation_control.run_if(resource_exists::<Animations>()),
+                (
+                    track_current_animation,
+                    keyboard_animation_control.run_if(ui::keyboard_free),
+                )
+                    .chain()
+                    .run_if(resource_exists::<Animations>()),
+                draw_gizmos,
+                (
+                    keyboard_model_offset.run_if(ui::keyboard_free),
+                    apply_model_offset.run_if(resource_changed::<ModelConfig>()),
+                )
+                    .chain(),
             ),
-        )
-        .run();
+        );
+
+    if let Some(project) = project {
+        project.apply(&mut app);
+    }
+    if let Some(dock) = ui::UiDock::from_args() {
+        app.world.resource_mut::<ui::UiSettings>().dock = dock;
+    }
+    if let Some(export) = capture::BatchExport::from_args() {
+        println!(
+            "exporting {} frames of every animation to {}",
+            export.frames, export.directory
+        );
+        app.insert_resource(export);
+    }
+    if let Some(model) = asset_arg("--model") {
+        let model = if model.contains('#') {
+            model
+        } else {
+            format!("{model}#Scene0")
+        };
+        app.world.resource_mut::<ModelConfig>().scene = model;
+    }
+    if let Some(scan) = scan::AnimationDirectoryScan::from_args() {
+        app.insert_resource(scan);
+        app.insert_resource(AnimationsFile(None));
+    }
+    if let Some(file) = asset_arg("--gltf") {
+        let file = file.split('#').next().unwrap_or_default().to_string();
+        app.world.resource_mut::<ModelConfig>().scene = format!("{file}#Scene0");
+        app.insert_resource(scan::AnimationDirectoryScan::single_file(&file));
+        app.insert_resource(AnimationsFile(None));
+    }
+    if let Some(file) = asset_arg("--animations") {
+        app.insert_resource(AnimationsFile(None));
+        for params in &mut app.world.resource_mut::<AnimationsMetadata>().0 {
+            params.path = match params.path.split_once('#') {
+                Some((_, label)) => format!("{file}#{label}"),
+                None => file.clone(),
+            };
+        }
+    }
+
+    #[cfg(feature = "bvh")]
+    app.add_plugins(bvh::BvhImportPlugin);
+
+    app.run();
 }
 
 #[derive(Resource)]
@@ -81,10 +353,42 @@ struct Animations(Vec<Handle<AnimationClip>>);
 #[derive(Resource)]
 struct AnimationsLoadedMarker;
 
+/// Index into `Animations` and `AnimationsMetadata` of the clip playing.
+#[derive(Resource, Default, Debug)]
+pub struct CurrentAnimation(pub usize);
+
+/// Marks the spawned character scene. `base` is the transform it was spawned
+/// with; user adjustments are composed on top of it.
+#[derive(Component)]
+pub struct ModelRoot {
+    pub base: Transform,
+}
+
+#[derive(Resource, Debug)]
+pub struct ModelConfig {
+    /// Scene asset spawned as the character.
+    pub scene: String,
+    /// Translation added to the model's spawn position.
+    pub offset: Vec3,
+    pub nudge_step: f32,
+}
+
+impl Default for ModelConfig {
+    fn default() -> Self {
+        Self {
+            // scene: "mixamo_character_1.glb#Scene0".to_string(),
+            scene: "mixamo_character_2.glb#Scene0".to_string(),
+            offset: Vec3::ZERO,
+            nudge_step: 0.05,
+        }
+    }
+}
+
 fn setup(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     animation_meta: Res<AnimationsMetadata>,
+    model_config: Res<ModelConfig>,
 ) {
     println!("--------- setup");
 
@@ -126,18 +430,69 @@ fn setup(
     // Fox
     let mut trans = Transform::default();
     trans.rotate_axis(Vec3::Y, 3.14159 * 0.5);
-    commands.spawn(SceneBundle {
-        // scene: asset_server.load("mixamo_character_1.glb#Scene0"),
-        scene: asset_server.load("mixamo_character_2.glb#Scene0"),
-        transform: trans,
-        ..default()
-    });
+    commands.spawn((
+        SceneBundle {
+            scene: asset_server.load(&model_config.scene),
+            transform: trans,
+            ..default()
+        },
+        ModelRoot { base: trans },
+    ));
 
     println!("Animation controls:");
+    println!("  (playback and overlay keys can be remapped in {KEYBINDINGS_PATH})");
     println!("  - spacebar: play / pause");
-    println!("  - arrow up / down: speed up / slow down animation playback");
-    println!("  - arrow left / right: seek backward / forward");
-    println!("  - return: change animation");
+    println!("  - arrow up / down: speed up / slow down the reference lines; 0: stop them");
+    println!("  - A / Z: speed up / slow down animation playback");
+    println!("  - R: toggle reverse playback");
+    println!("  - shift+S: save the animation list, with speeds set by A / Z, to animations.ron");
+    println!("  - arrow left / right: seek to the previous / next frame");
+    println!("  - , / . (paused): step one frame backward / forward");
+    println!("  - home / end: pause on the first / last frame");
+    println!("  - 1-9 / shift+1-9: bookmark the current time / jump to a bookmark");
+    println!("  - return: change animation (among those matching the list filter)");
+    println!("  - T: snap back to the T-pose");
+    println!("  - L: cycle loop mode (once / loop / ping-pong)");
+    println!("  - I / U: loop from / to the current time; shift+I / shift+U: clear");
+    println!("  - F9: export a contact sheet of the current animation");
+    println!("  - = / -: grow / shrink the ui");
+    println!("  - J: toggle spring secondary motion");
+    println!("  - F2: toggle local / world space in the bone inspector");
+    println!("  - C / shift+C: toggle center of mass / its trail");
+    println!("  - [ / ]: toggle the ground grid / the scrolling reference lines");
+    println!("  - B: toggle the skeleton overlay");
+    println!("  - \\: toggle the root motion velocity arrow");
+    println!("  - O / shift+O: toggle bone axes / only for the inspected bone");
+    println!("  - E: toggle ramping playback speed up on every loop");
+    println!("  - ;: toggle the bone proportion preview");
+    println!("  - ': toggle blending the shift-clicked clip as an override / additive layer");
+    println!("  - D: toggle backing the camera off to avoid clipping the model");
+    println!("  - numpad 4 / 6, 8 / 2, + / -: move the model along x, y, z; numpad 0: reset");
+    println!("  - K: toggle ik chains");
+    println!("  - H: toggle turning the head towards the look-at target");
+    println!("  - P: save the current pose to a .ron file");
+    println!("  - F3 / F4: capture pose A / B for the pose comparison");
+    println!("  - F5: toggle blending from the live pose when switching animations");
+    println!("  - F6: play the configured fractional number of loops, then hold");
+    println!("  - F7 / M: toggle advancing to the next animation on a timer");
+    println!("  - F8: toggle ghosts comparing the animation at two speeds");
+    println!("  - F10: export the posed mesh as an obj");
+    println!("  - F11: export one loop of the current animation as a sprite sheet");
+    println!("  - F12: export one loop of the current animation as numbered PNGs");
+    println!("  - Q: toggle slowly orbiting the light");
+    println!("  - W: toggle drawing the character as a wireframe");
+    println!("  - X: toggle playing the animation mirrored left to right");
+    println!("  - G: cycle slow motion (1x / 0.5x / 0.25x / 0.1x) over the clip speeds");
+    println!("  - tab: toggle keeping every animation player in sync with the first");
+    println!("  - V: toggle comparing the animation against its mirror image");
+    println!("  - Y: toggle holding key poses instead of interpolating");
+    println!("  - F (hold): fast-forward");
+    println!("  - /: toggle the foot sliding readout");
+    println!("  - ` + right-click / drag: add / move motion path points; delete: clear the path");
+    println!("  - F1: toggle the studio lighting setup");
+    println!("  - mouse: left-drag to orbit, middle-drag to pan, wheel to zoom");
+    println!("  - N / shift+N: cycle camera presets / save the camera as a preset");
+    println!("  - numpad 1 / 3 / 7 / 5: front / side / top / three-quarter view");
 }
 
 // Once the scene is loaded, start the animation
@@ -146,24 +501,83 @@ fn setup_scene_once_loaded(
     mut players: Query<&mut AnimationPlayer, Added<AnimationPlayer>>,
 ) {
     for mut player in &mut players {
-        player.play(animations.0[0].clone_weak()).repeat();
+        player.play(animations.0[0].clone_weak());
     }
 }
 
-fn keyboard_animation_control(
-    keyboard_input: Res<Input<KeyCode>>,
-    mut animation_players: Query<&mut AnimationPlayer>,
-    animations: Res<Animations>,
-    animation_meta: Res<AnimationsMetadata>,
+/// Fastest the reference lines scroll either way, in meters per second.
+pub const LINE_SPEED_LIMIT: f32 = 5.0;
+
+/// Toggles and parameters for the debug gizmos drawn over the scene.
+#[derive(Resource, Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct GizmosConfig {
+    /// Scroll speed of the reference lines, which are a meter apart, in
+    /// meters per second. Matching it to the feet reads off ground speed.
+    pub vel: f32,
+    /// Scroll the reference lines vertically instead of horizontally.
+    pub y: bool,
+    /// Draw the scrolling reference lines and the swaying height box.
+    pub show_scroll_lines: bool,
+    /// Draw a grid on the ground and the world axes at the origin.
+    pub show_grid: bool,
+    /// Distance from the origin to the edge of the grid.
+    pub grid_extent: f32,
+    pub grid_spacing: f32,
+    pub show_center_of_mass: bool,
+    pub show_center_of_mass_trail: bool,
+    pub show_bone_axes: bool,
+    /// Only draw axes for the bone selected in the inspector.
+    pub bone_axes_selected_only: bool,
+    pub bone_axes_scale: f32,
+    /// Draw lines between the joints and a sphere at each one.
+    pub show_skeleton: bool,
+    pub joint_radius: f32,
+    /// Draw the root bone's measured velocity from the model origin.
+    pub show_root_motion: bool,
+    /// World-space velocity of the root bone over the last frame.
+    #[serde(skip)]
+    pub root_velocity: Vec3,
+    /// Draw the angular velocity of the bone selected in the inspector.
+    pub show_angular_velocity: bool,
+    /// Length of the axis indicator per radian per second.
+    pub angular_velocity_scale: f32,
+    /// Draw the direction the sun shines in through the origin.
+    pub show_sun_direction: bool,
+}
 
-    mut gizmos: Gizmos,
-    time: Res<Time>,
-    //locals
-    mut current_animation: Local<usize>,
-    mut vel: Local<f32>,
-    mut gizmos_y: Local<bool>,
-    mut use_params: Local<bool>,
-) {
+impl Default for GizmosConfig {
+    fn default() -> Self {
+        Self {
+            vel: 0.0,
+            y: false,
+            show_scroll_lines: true,
+            show_grid: true,
+            grid_extent: 5.0,
+            grid_spacing: 0.5,
+            show_center_of_mass: false,
+            show_center_of_mass_trail: false,
+            show_bone_axes: false,
+            bone_axes_selected_only: false,
+            bone_axes_scale: 0.08,
+            show_skeleton: false,
+            joint_radius: 0.015,
+            show_root_motion: false,
+            root_velocity: Vec3::ZERO,
+            show_angular_velocity: false,
+            angular_velocity_scale: 0.02,
+            show_sun_direction: false,
+        }
+    }
+}
+
+fn draw_gizmos(mut gizmos: Gizmos, time: Res<Time>, config: Res<GizmosConfig>) {
+    if config.show_grid {
+        draw_grid(&mut gizmos, &config);
+    }
+    if !config.show_scroll_lines {
+        return;
+    }
     gizmos.rect(
         Vec3::Y * (1.7 / 2.0) + Vec3::Z * time.elapsed_seconds().sin(),
         Quat::from_rotation_y(0.0),
@@ -173,11 +587,11 @@ fn keyboard_animation_control(
     let num_lines = 30;
     for i in 0..num_lines {
         let t = time.elapsed_seconds();
-        let mut x = -t * *vel + i as f32;
+        let mut x = -t * config.vel + i as f32;
 
         x = x % num_lines as f32 - (num_lines as f32 / 2.0) * x.signum();
 
-        let (v, end) = if *gizmos_y {
+        let (v, end) = if config.y {
             (Vec3::Y * x, Vec3::X)
         } else {
             (Vec3::X * x, Vec3::NEG_Y)
@@ -185,13 +599,137 @@ fn keyboard_animation_control(
 
         gizmos.ray(v, end, Color::BISQUE)
     }
+}
 
-    if keyboard_input.just_pressed(KeyCode::Back) {
-        *gizmos_y = !*gizmos_y;
+/// Lines on the ground every `grid_spacing` meters, with the world axes drawn
+/// from the origin in red (x), green (y) and blue (z).
+fn draw_grid(gizmos: &mut Gizmos, config: &GizmosConfig) {
+    let extent = config.grid_extent;
+    let lines = (extent / config.grid_spacing.max(0.01)).floor() as i32;
+    for i in -lines..=lines {
+        let offset = i as f32 * config.grid_spacing;
+        let color = Color::GRAY.with_a(if i == 0 { 0.6 } else { 0.25 });
+        gizmos.line(
+            Vec3::new(offset, 0.0, -extent),
+            Vec3::new(offset, 0.0, extent),
+            color,
+        );
+        gizmos.line(
+            Vec3::new(-extent, 0.0, offset),
+            Vec3::new(extent, 0.0, offset),
+            color,
+        );
+    }
+    gizmos.line(Vec3::ZERO, Vec3::X, Color::RED);
+    gizmos.line(Vec3::ZERO, Vec3::Y, Color::GREEN);
+    gizmos.line(Vec3::ZERO, Vec3::Z, Color::BLUE);
+}
+
+fn keyboard_model_offset(keyboard_input: Res<Input<KeyCode>>, mut config: ResMut<ModelConfig>) {
+    let axes = [
+        (KeyCode::Numpad4, KeyCode::Numpad6, Vec3::X),
+        (KeyCode::Numpad2, KeyCode::Numpad8, Vec3::Y),
+        (KeyCode::NumpadSubtract, KeyCode::NumpadAdd, Vec3::Z),
+    ];
+    let mut nudge = Vec3::ZERO;
+    for (negative, positive, axis) in axes {
+        if keyboard_input.just_pressed(negative) {
+            nudge -= axis;
+        }
+        if keyboard_input.just_pressed(positive) {
+            nudge += axis;
+        }
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Numpad0) {
+        config.offset = Vec3::ZERO;
+        println!("model offset reset");
+    } else if nudge != Vec3::ZERO {
+        config.offset += nudge * config.nudge_step;
+        println!("model offset: {}", config.offset);
+    }
+}
+
+fn apply_model_offset(config: Res<ModelConfig>, mut roots: Query<(&mut Transform, &ModelRoot)>) {
+    for (mut transform, root) in &mut roots {
+        transform.translation = root.base.translation + config.offset;
+    }
+}
+
+fn keyboard_animation_control(
+    keyboard_input: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut animation_players: Query<&mut AnimationPlayer>,
+    animations: Res<Animations>,
+    mut animation_meta: ResMut<AnimationsMetadata>,
+    animations_file: Res<AnimationsFile>,
+    mut gizmos_config: ResMut<GizmosConfig>,
+    live_pose_blend: Res<poses::LivePoseBlend>,
+    mut current_animation: ResMut<CurrentAnimation>,
+    frame_step: Res<playback::FrameStepConfig>,
+    speed_limits: Res<playback::SpeedLimits>,
+    mut smooth_speed: ResMut<playback::SmoothSpeed>,
+    clips: Res<Assets<AnimationClip>>,
+    clip_filter: Res<clip_list::ClipFilter>,
+    //locals
+    mut use_params: Local<bool>,
+) {
+    if bindings.just_pressed(Action::ToggleLineAxis, &keyboard_input) {
+        gizmos_config.y = !gizmos_config.y;
+    }
+
+    if bindings.just_pressed(Action::ToggleGrid, &keyboard_input) {
+        gizmos_config.show_grid = !gizmos_config.show_grid;
+        println!("ground grid: {}", gizmos_config.show_grid);
+    }
+
+    if bindings.just_pressed(Action::ToggleScrollLines, &keyboard_input) {
+        gizmos_config.show_scroll_lines = !gizmos_config.show_scroll_lines;
+        println!("scroll lines: {}", gizmos_config.show_scroll_lines);
+    }
+
+    if bindings.just_pressed(Action::ToggleCenterOfMass, &keyboard_input) {
+        if keyboard_input.pressed(KeyCode::ShiftLeft) {
+            gizmos_config.show_center_of_mass_trail = !gizmos_config.show_center_of_mass_trail;
+        } else {
+            gizmos_config.show_center_of_mass = !gizmos_config.show_center_of_mass;
+        }
+    }
+
+    if bindings.just_pressed(Action::ToggleSkeleton, &keyboard_input) {
+        gizmos_config.show_skeleton = !gizmos_config.show_skeleton;
+        println!("skeleton: {}", gizmos_config.show_skeleton);
+    }
+
+    if bindings.just_pressed(Action::ToggleRootMotion, &keyboard_input) {
+        gizmos_config.show_root_motion = !gizmos_config.show_root_motion;
+        println!("root motion: {}", gizmos_config.show_root_motion);
+    }
+
+    if bindings.just_pressed(Action::ToggleBoneAxes, &keyboard_input) {
+        if keyboard_input.pressed(KeyCode::ShiftLeft) {
+            gizmos_config.bone_axes_selected_only = !gizmos_config.bone_axes_selected_only;
+        } else {
+            gizmos_config.show_bone_axes = !gizmos_config.show_bone_axes;
+        }
+    }
+
+    if bindings.just_pressed(Action::SaveParams, &keyboard_input)
+        && keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+    {
+        match &animations_file.0 {
+            Some(path) => match animation_meta.save(path) {
+                Ok(()) => println!("saved animation params to {path}"),
+                Err(e) => println!("could not save {path}: {e}"),
+            },
+            None => {
+                println!("not saving animation params: the list didn't come from {ANIMATIONS_PATH}")
+            }
+        }
     }
 
     for mut player in &mut animation_players {
-        if keyboard_input.just_pressed(KeyCode::Space) {
+        if bindings.just_pressed(Action::PlayPause, &keyboard_input) {
             if player.is_paused() {
                 player.resume();
             } else {
@@ -199,71 +737,192 @@ fn keyboard_animation_control(
             }
         }
 
-        if keyboard_input.just_pressed(KeyCode::Up) {
-            *vel += 0.1;
-            println!("playback speed: {},   vel: {}", player.speed(), *vel);
+        if bindings.just_pressed(Action::LineSpeedUp, &keyboard_input) {
+            gizmos_config.vel = (gizmos_config.vel + 0.1).min(LINE_SPEED_LIMIT);
+            println!(
+                "playback speed: {},   vel: {}",
+                player.speed(),
+                gizmos_config.vel
+            );
         }
 
-        if keyboard_input.just_pressed(KeyCode::Down) {
-            *vel -= 0.1;
-            println!("playback speed: {},   vel: {}", player.speed(), *vel);
+        if bindings.just_pressed(Action::LineSpeedDown, &keyboard_input) {
+            gizmos_config.vel = (gizmos_config.vel - 0.1).max(-LINE_SPEED_LIMIT);
+            println!(
+                "playback speed: {},   vel: {}",
+                player.speed(),
+                gizmos_config.vel
+            );
+        }
+
+        if bindings.just_pressed(Action::ResetLineSpeed, &keyboard_input) {
+            gizmos_config.vel = 0.0;
+            println!("vel: 0");
         }
 
-        if keyboard_input.just_pressed(KeyCode::ControlLeft) {
+        if bindings.just_pressed(Action::ToggleParams, &keyboard_input) {
             *use_params = !*use_params;
         }
 
         if *use_params {
-            let anim_params = &animation_meta.0[*current_animation];
-            let speed = anim_params.playback_speed;
+            let anim_params = &animation_meta.0[current_animation.0];
+            // Keep the direction chosen with R.
+            let speed = anim_params.playback_speed.copysign(player.speed());
             player.set_speed(speed);
         } else {
-            if keyboard_input.just_pressed(KeyCode::A) {
-                let speed = player.speed();
-                player.set_speed(speed + 0.1);
-                println!("playback speed: {},   vel: {}", player.speed(), *vel);
+            if bindings.just_pressed(Action::SpeedUp, &keyboard_input) {
+                let speed = speed_limits.adjust(smooth_speed.target_or(player.speed()), 0.1);
+                smooth_speed.ease_to(player.speed(), speed);
+                animation_meta.0[current_animation.0].playback_speed = speed.abs();
+                println!("playback speed: {},   vel: {}", speed, gizmos_config.vel);
             }
 
-            if keyboard_input.just_pressed(KeyCode::Z) {
-                let speed = player.speed();
-                player.set_speed(speed - 0.1);
-                println!("playback speed: {},   vel: {}", player.speed(), *vel);
+            if bindings.just_pressed(Action::SpeedDown, &keyboard_input) {
+                let speed = speed_limits.adjust(smooth_speed.target_or(player.speed()), -0.1);
+                smooth_speed.ease_to(player.speed(), speed);
+                animation_meta.0[current_animation.0].playback_speed = speed.abs();
+                println!("playback speed: {},   vel: {}", speed, gizmos_config.vel);
+            }
+        }
+
+        if bindings.just_pressed(Action::Reverse, &keyboard_input) {
+            // Reversing is deliberate, so it flips at once rather than easing.
+            let speed = -smooth_speed.target_or(player.speed());
+            smooth_speed.cancel();
+            player.set_speed(speed);
+            if speed < 0.0 {
+                println!("reverse playback: speed {speed}");
+            } else {
+                println!("forward playback: speed {speed}");
             }
         }
 
-        if keyboard_input.just_pressed(KeyCode::ControlLeft) {
+        if bindings.just_pressed(Action::ToggleParams, &keyboard_input) {
             println!(
                 "TOGGLED PARAMS {} playback speed: {},   vel: {}",
                 *use_params,
                 player.speed(),
-                *vel
+                gizmos_config.vel
             );
         }
 
-        if keyboard_input.just_pressed(KeyCode::Left) {
-            let elapsed = player.elapsed();
-            player.seek_to(elapsed - 0.1);
+        let duration = clips
+            .get(player.animation_clip())
+            .map_or(0.0, |clip| clip.duration());
+
+        let frame = if bindings.just_pressed(Action::SeekBackward, &keyboard_input) {
+            Some(frame_step.previous_frame(player.seek_time()))
+        } else if bindings.just_pressed(Action::SeekForward, &keyboard_input) {
+            Some(frame_step.next_frame(player.seek_time()))
+        } else {
+            None
+        };
+        if let Some(frame) = frame {
+            let time = frame_step.frame_time(frame, duration);
+            player.seek_to(time);
+            println!("frame {} ({time:.3}s)", frame_step.frame_at(time));
         }
 
-        if keyboard_input.just_pressed(KeyCode::Right) {
-            let elapsed = player.elapsed();
-            player.seek_to(elapsed + 0.1);
+        let boundary = if bindings.just_pressed(Action::FirstFrame, &keyboard_input) {
+            Some(0.0)
+        } else if bindings.just_pressed(Action::LastFrame, &keyboard_input) {
+            Some(duration)
+        } else {
+            None
+        };
+        if let Some(time) = boundary {
+            player.pause();
+            player.seek_to(time);
+            println!("paused at {time:.3}s");
         }
 
-        if keyboard_input.just_pressed(KeyCode::Return) {
-            *current_animation = (*current_animation + 1) % animations.0.len();
-            player
-                .play_with_transition(
-                    animations.0[*current_animation].clone_weak(),
-                    Duration::from_millis(250),
-                )
-                .repeat();
+        let frames = if bindings.just_pressed(Action::StepForward, &keyboard_input) {
+            1.0
+        } else if bindings.just_pressed(Action::StepBackward, &keyboard_input) {
+            -1.0
+        } else {
+            0.0
+        };
+        if frames != 0.0 && player.is_paused() {
+            let mut time = player.seek_time() + frames / frame_step.fps;
+            if player.repeat_mode() == RepeatAnimation::Never {
+                time = time.clamp(0.0, duration);
+            } else if duration > 0.0 {
+                time = time.rem_euclid(duration);
+            }
+            player.seek_to(time);
+            println!(
+                "frame {} ({time:.3}s at {} fps)",
+                (time * frame_step.fps).round(),
+                frame_step.fps
+            );
+        }
+
+        if bindings.just_pressed(Action::TPose, &keyboard_input) {
+            current_animation.0 = animation_meta
+                .0
+                .iter()
+                .position(|params| params.name == "TPose")
+                .unwrap_or(0);
+            player.play(animations.0[current_animation.0].clone_weak());
+            println!(
+                "Reset to rest pose: {}",
+                animation_meta.0[current_animation.0].name
+            );
+        }
+
+        let next = clip_filter.next(current_animation.0, animations.0.len());
+        if let Some(next) =
+            next.filter(|_| bindings.just_pressed(Action::NextAnimation, &keyboard_input))
+        {
+            current_animation.0 = next;
+            play_animation(
+                &mut player,
+                &animations.0[current_animation.0],
+                &live_pose_blend,
+            );
 
             println!(
                 "Playing animation: {}",
-                animation_meta.0[*current_animation].name
+                animation_meta.0[current_animation.0].name
             );
-            println!("{:?}", animation_meta.0[*current_animation]);
+            println!("{:?}", animation_meta.0[current_animation.0]);
+        }
+    }
+}
+
+/// Switches `player` to `clip` with a short crossfade. How it repeats is up
+/// to `playback::LoopMode`.
+pub fn play_animation(
+    player: &mut AnimationPlayer,
+    clip: &Handle<AnimationClip>,
+    live_pose_blend: &poses::LivePoseBlend,
+) {
+    if live_pose_blend.enabled {
+        // The live pose blend takes over the crossfade.
+        player.play(clip.clone_weak());
+    } else {
+        player.play_with_transition(clip.clone_weak(), Duration::from_millis(250));
+    }
+}
+
+/// Follows whatever clip is playing, since tools other than the keyboard
+/// controls switch clips too.
+fn track_current_animation(
+    animations: Res<Animations>,
+    players: Query<&AnimationPlayer>,
+    mut current_animation: ResMut<CurrentAnimation>,
+) {
+    let Some(clip) = players
+        .iter()
+        .next()
+        .map(|player| player.animation_clip().id())
+    else {
+        return;
+    };
+    if let Some(index) = animations.0.iter().position(|handle| handle.id() == clip) {
+        if current_animation.0 != index {
+            current_animation.0 = index;
         }
     }
 }
@@ -0,0 +1,523 @@
+//! Gizmo overlays computed from the posed skeleton.
+
+use std::collections::VecDeque;
+use std::f32::consts::{PI, TAU};
+
+use bevy::core::FrameCount;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+use bevy_inspector_egui::egui;
+
+use crate::inspector::BoneInspector;
+use crate::skeleton::Skeleton;
+use crate::{GizmosConfig, ModelRoot, LINE_SPEED_LIMIT};
+
+#[derive(Resource, Debug)]
+pub struct CenterOfMassConfig {
+    /// Per-bone weights by bone name; bones not listed weigh `1.0`.
+    pub weights: HashMap<String, f32>,
+    /// Number of past positions kept for the trail.
+    pub trail_length: usize,
+}
+
+impl Default for CenterOfMassConfig {
+    fn default() -> Self {
+        Self {
+            weights: HashMap::default(),
+            trail_length: 120,
+        }
+    }
+}
+
+/// The last computed center of mass and its recent history.
+#[derive(Resource, Default, Debug)]
+pub struct CenterOfMass {
+    pub position: Option<Vec3>,
+    pub trail: VecDeque<Vec3>,
+}
+
+/// World-space angular velocity of the bone selected in the inspector, from
+/// the change in its rotation since the previous frame.
+#[derive(Resource, Default, Debug)]
+pub struct AngularVelocity {
+    pub bone: Option<String>,
+    previous: Option<(PlaybackStamp, Quat)>,
+    /// Unit rotation axis; rotation follows the right-hand rule.
+    pub axis: Vec3,
+    /// Radians per second.
+    pub speed: f32,
+}
+
+/// Where playback was when a velocity overlay last sampled the pose. A
+/// velocity is only worked out between samples that follow on from each
+/// other, not across a clip switch, a wrap or seek back, or frames the
+/// overlay was switched off for.
+#[derive(Debug, Clone, Copy)]
+struct PlaybackStamp {
+    frame: u32,
+    clip: AssetId<AnimationClip>,
+    completions: u32,
+    seek_time: f32,
+    speed: f32,
+}
+
+impl PlaybackStamp {
+    fn new(frame: &FrameCount, player: &AnimationPlayer) -> Self {
+        Self {
+            frame: frame.0,
+            clip: player.animation_clip().id(),
+            completions: player.completions(),
+            seek_time: player.seek_time(),
+            speed: player.speed(),
+        }
+    }
+
+    fn follows(&self, previous: &PlaybackStamp) -> bool {
+        self.frame == previous.frame.wrapping_add(1)
+            && self.clip == previous.clip
+            && self.completions == previous.completions
+            && (self.seek_time - previous.seek_time) * self.speed >= 0.0
+    }
+}
+
+/// Recent world positions of one bone, drawn as a fading line to judge the
+/// arcs of a hand or foot.
+#[derive(Resource, Debug)]
+pub struct BoneTrail {
+    pub bone: Option<String>,
+    /// Number of past positions kept.
+    pub length: usize,
+    pub points: VecDeque<Vec3>,
+    /// Clip the points were recorded from; switching clips starts over.
+    clip: Option<AssetId<AnimationClip>>,
+}
+
+impl Default for BoneTrail {
+    fn default() -> Self {
+        Self {
+            bone: None,
+            length: 90,
+            points: VecDeque::new(),
+            clip: None,
+        }
+    }
+}
+
+pub struct OverlaysPlugin;
+
+impl Plugin for OverlaysPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CenterOfMassConfig>()
+            .init_resource::<CenterOfMass>()
+            .init_resource::<AngularVelocity>()
+            .init_resource::<BoneTrail>()
+            .add_systems(
+                Update,
+                (
+                    reference_lines_panel,
+                    (
+                        bone_trail_panel,
+                        (update_bone_trail, draw_bone_trail)
+                            .chain()
+                            .run_if(|trail: Res<BoneTrail>| trail.bone.is_some()),
+                    )
+                        .chain(),
+                    (update_center_of_mass, draw_center_of_mass)
+                        .chain()
+                        .run_if(|config: Res<GizmosConfig>| config.show_center_of_mass),
+                    draw_skeleton.run_if(
+                        |config: Res<GizmosConfig>, inspector: Res<BoneInspector>| {
+                            config.show_skeleton || inspector.soloed().is_some()
+                        },
+                    ),
+                    draw_bone_axes.run_if(|config: Res<GizmosConfig>| config.show_bone_axes),
+                    (update_root_motion, draw_root_motion)
+                        .chain()
+                        .run_if(|config: Res<GizmosConfig>| config.show_root_motion),
+                    (update_angular_velocity, draw_angular_velocity)
+                        .chain()
+                        .run_if(|config: Res<GizmosConfig>| config.show_angular_velocity),
+                ),
+            );
+    }
+}
+
+/// Weighted average of the bones' world positions.
+pub fn weighted_center(
+    skeleton: &Skeleton,
+    weights: &HashMap<String, f32>,
+    global_transforms: &Query<&GlobalTransform>,
+) -> Option<Vec3> {
+    let (sum, total_weight) = skeleton
+        .bones
+        .iter()
+        .filter_map(|bone| {
+            let position = global_transforms.get(bone.entity).ok()?.translation();
+            let weight = weights.get(&bone.name).copied().unwrap_or(1.0);
+            Some((position * weight, weight))
+        })
+        .fold((Vec3::ZERO, 0.0), |(sum, total), (p, w)| {
+            (sum + p, total + w)
+        });
+    (total_weight > 0.0).then(|| sum / total_weight)
+}
+
+fn update_center_of_mass(
+    config: Res<CenterOfMassConfig>,
+    skeleton: Res<Skeleton>,
+    global_transforms: Query<&GlobalTransform>,
+    mut center_of_mass: ResMut<CenterOfMass>,
+) {
+    let position = weighted_center(&skeleton, &config.weights, &global_transforms);
+    center_of_mass.position = position;
+    if let Some(position) = position {
+        center_of_mass.trail.push_back(position);
+        while center_of_mass.trail.len() > config.trail_length {
+            center_of_mass.trail.pop_front();
+        }
+    }
+}
+
+fn draw_center_of_mass(
+    mut gizmos: Gizmos,
+    gizmos_config: Res<GizmosConfig>,
+    center_of_mass: Res<CenterOfMass>,
+) {
+    let Some(position) = center_of_mass.position else {
+        return;
+    };
+    gizmos.sphere(position, Quat::IDENTITY, 0.05, Color::FUCHSIA);
+    // Projected onto the ground, to see whether it's over the support foot.
+    let ground = Vec3::new(position.x, 0.0, position.z);
+    gizmos.line(position, ground, Color::FUCHSIA);
+    gizmos.circle(ground, Vec3::Y, 0.05, Color::FUCHSIA);
+
+    if gizmos_config.show_center_of_mass_trail {
+        let len = center_of_mass.trail.len().max(1) as f32;
+        gizmos.linestrip_gradient(
+            center_of_mass
+                .trail
+                .iter()
+                .enumerate()
+                .map(|(i, &p)| (p, Color::FUCHSIA.with_a(i as f32 / len))),
+        );
+    }
+}
+
+fn reference_lines_panel(mut contexts: EguiContexts, mut config: ResMut<GizmosConfig>) {
+    egui::Window::new("Reference lines")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.checkbox(&mut config.show_scroll_lines, "show (])");
+            ui.add(
+                egui::Slider::new(&mut config.vel, -LINE_SPEED_LIMIT..=LINE_SPEED_LIMIT)
+                    .text("speed (m/s)"),
+            );
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut config.y, "scroll vertically (backspace)");
+                if ui.button("reset speed (0)").clicked() {
+                    config.vel = 0.0;
+                }
+            });
+        });
+}
+
+fn bone_trail_panel(
+    mut contexts: EguiContexts,
+    skeleton: Res<Skeleton>,
+    mut trail: ResMut<BoneTrail>,
+) {
+    let previous = trail.bone.clone();
+    egui::Window::new("Bone trail")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            egui::ComboBox::from_label("bone")
+                .selected_text(trail.bone.as_deref().unwrap_or("none"))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut trail.bone, None, "none");
+                    for bone in &skeleton.bones {
+                        ui.selectable_value(
+                            &mut trail.bone,
+                            Some(bone.name.clone()),
+                            bone.name.as_str(),
+                        );
+                    }
+                });
+            ui.add(egui::Slider::new(&mut trail.length, 2..=600).text("frames"));
+            if ui.button("clear").clicked() {
+                trail.points.clear();
+            }
+        });
+    if trail.bone != previous {
+        trail.points.clear();
+    }
+}
+
+fn update_bone_trail(
+    skeleton: Res<Skeleton>,
+    players: Query<&AnimationPlayer>,
+    global_transforms: Query<&GlobalTransform>,
+    mut trail: ResMut<BoneTrail>,
+) {
+    let clip = players
+        .iter()
+        .next()
+        .map(|player| player.animation_clip().id());
+    if clip != trail.clip {
+        trail.clip = clip;
+        trail.points.clear();
+    }
+    let Some(position) = trail
+        .bone
+        .as_deref()
+        .and_then(|name| skeleton.find(name))
+        .and_then(|bone| global_transforms.get(bone.entity).ok())
+        .map(|global| global.translation())
+    else {
+        return;
+    };
+    trail.points.push_back(position);
+    while trail.points.len() > trail.length {
+        trail.points.pop_front();
+    }
+}
+
+fn draw_bone_trail(mut gizmos: Gizmos, trail: Res<BoneTrail>) {
+    let len = trail.points.len().max(1) as f32;
+    gizmos.linestrip_gradient(
+        trail
+            .points
+            .iter()
+            .enumerate()
+            .map(|(i, &p)| (p, Color::YELLOW.with_a((i + 1) as f32 / len))),
+    );
+}
+
+/// Draws the posed skeleton: a sphere at each joint and a line to its parent
+/// joint. A soloed bone is drawn large with bright lines to its children,
+/// over the rest of the skeleton dimmed.
+fn draw_skeleton(
+    mut gizmos: Gizmos,
+    config: Res<GizmosConfig>,
+    inspector: Res<BoneInspector>,
+    skeleton: Res<Skeleton>,
+    global_transforms: Query<&GlobalTransform>,
+) {
+    let joints: Vec<Option<Vec3>> = skeleton
+        .bones
+        .iter()
+        .map(|bone| {
+            global_transforms
+                .get(bone.entity)
+                .ok()
+                .map(|global| global.translation())
+        })
+        .collect();
+    let solo = inspector.soloed().and_then(|name| skeleton.index_of(name));
+    let color = if solo.is_some() {
+        Color::WHITE.with_a(0.15)
+    } else {
+        Color::WHITE
+    };
+    for (bone, joint) in skeleton.bones.iter().zip(&joints) {
+        let Some(joint) = *joint else {
+            continue;
+        };
+        gizmos.sphere(joint, Quat::IDENTITY, config.joint_radius, color);
+        if let Some(parent) = bone.parent.and_then(|parent| joints[parent]) {
+            gizmos.line(parent, joint, color);
+        }
+    }
+
+    let Some((index, Some(joint))) = solo.map(|index| (index, joints[index])) else {
+        return;
+    };
+    gizmos.sphere(
+        joint,
+        Quat::IDENTITY,
+        config.joint_radius * 3.0,
+        Color::YELLOW,
+    );
+    for child in skeleton.children_of(index) {
+        if let Some(child) = joints[child] {
+            gizmos.line(joint, child, Color::YELLOW);
+        }
+    }
+}
+
+/// Draws each bone's local X/Y/Z axes in red/green/blue.
+fn draw_bone_axes(
+    mut gizmos: Gizmos,
+    config: Res<GizmosConfig>,
+    skeleton: Res<Skeleton>,
+    inspector: Res<BoneInspector>,
+    global_transforms: Query<&GlobalTransform>,
+) {
+    for bone in &skeleton.bones {
+        if config.bone_axes_selected_only
+            && inspector.selected.as_deref() != Some(bone.name.as_str())
+        {
+            continue;
+        }
+        let Ok(global) = global_transforms.get(bone.entity) else {
+            continue;
+        };
+        let (_, rotation, origin) = global.to_scale_rotation_translation();
+        let scale = config.bone_axes_scale;
+        gizmos.ray(origin, rotation * Vec3::X * scale, Color::RED);
+        gizmos.ray(origin, rotation * Vec3::Y * scale, Color::GREEN);
+        gizmos.ray(origin, rotation * Vec3::Z * scale, Color::BLUE);
+    }
+}
+
+/// The skeleton's root bone, which carries any root motion baked into a clip.
+fn root_bone(skeleton: &Skeleton) -> Option<Entity> {
+    skeleton
+        .find("Hips")
+        .or_else(|| skeleton.bones.iter().find(|bone| bone.parent.is_none()))
+        .map(|bone| bone.entity)
+}
+
+fn update_root_motion(
+    time: Res<Time>,
+    frame: Res<FrameCount>,
+    skeleton: Res<Skeleton>,
+    players: Query<&AnimationPlayer>,
+    global_transforms: Query<&GlobalTransform>,
+    mut config: ResMut<GizmosConfig>,
+    mut previous: Local<Option<(PlaybackStamp, Vec3)>>,
+) {
+    let Some(player) = players.iter().next() else {
+        return;
+    };
+    let Some(position) = root_bone(&skeleton)
+        .and_then(|root| global_transforms.get(root).ok())
+        .map(|global| global.translation())
+    else {
+        return;
+    };
+    let stamp = PlaybackStamp::new(&frame, player);
+    let dt = time.delta_seconds();
+    if let Some((last, previous)) = previous.replace((stamp, position)) {
+        if stamp.follows(&last) && dt > 0.0 {
+            config.root_velocity = (position - previous) / dt;
+        }
+    }
+}
+
+/// Draws the root velocity as an arrow from the model origin covering the
+/// distance travelled in a quarter second, with a readout of the speed.
+fn draw_root_motion(
+    mut gizmos: Gizmos,
+    mut contexts: EguiContexts,
+    config: Res<GizmosConfig>,
+    roots: Query<&GlobalTransform, With<ModelRoot>>,
+) {
+    let velocity = config.root_velocity;
+    egui::Window::new("Root motion")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            let horizontal = Vec2::new(velocity.x, velocity.z).length();
+            ui.label(format!("speed: {:.2} m/s", velocity.length()));
+            ui.label(format!("ground speed: {horizontal:.2} m/s"));
+        });
+
+    let Some(origin) = roots.iter().next().map(|root| root.translation()) else {
+        return;
+    };
+    let arrow = velocity * 0.25;
+    if arrow.length() <= f32::EPSILON {
+        return;
+    }
+    let tip = origin + arrow;
+    gizmos.line(origin, tip, Color::FUCHSIA);
+    let side = arrow.any_orthonormal_vector() * arrow.length() * 0.1;
+    let back = arrow * 0.2;
+    gizmos.line(tip, tip - back + side, Color::FUCHSIA);
+    gizmos.line(tip, tip - back - side, Color::FUCHSIA);
+}
+
+fn update_angular_velocity(
+    time: Res<Time>,
+    frame: Res<FrameCount>,
+    skeleton: Res<Skeleton>,
+    inspector: Res<BoneInspector>,
+    players: Query<&AnimationPlayer>,
+    global_transforms: Query<&GlobalTransform>,
+    mut velocity: ResMut<AngularVelocity>,
+) {
+    if velocity.bone != inspector.selected {
+        *velocity = AngularVelocity {
+            bone: inspector.selected.clone(),
+            ..default()
+        };
+    }
+    let Some(rotation) = velocity
+        .bone
+        .as_deref()
+        .and_then(|name| skeleton.find(name))
+        .and_then(|bone| global_transforms.get(bone.entity).ok())
+        .map(|global| global.to_scale_rotation_translation().1)
+    else {
+        return;
+    };
+    let Some(player) = players.iter().next() else {
+        return;
+    };
+    let stamp = PlaybackStamp::new(&frame, player);
+    let Some((last, previous)) = velocity.previous.replace((stamp, rotation)) else {
+        return;
+    };
+    let dt = time.delta_seconds();
+    if !stamp.follows(&last) || dt <= 0.0 {
+        return;
+    }
+
+    let (mut axis, mut angle) = (rotation * previous.inverse()).to_axis_angle();
+    // Take the short way around.
+    if angle > PI {
+        angle = TAU - angle;
+        axis = -axis;
+    }
+    velocity.axis = axis;
+    velocity.speed = angle / dt;
+}
+
+/// Draws the rotation axis scaled by angular speed, and an arc around it whose
+/// sweep grows with the speed and shows the direction of rotation.
+fn draw_angular_velocity(
+    mut gizmos: Gizmos,
+    config: Res<GizmosConfig>,
+    skeleton: Res<Skeleton>,
+    velocity: Res<AngularVelocity>,
+    global_transforms: Query<&GlobalTransform>,
+) {
+    let Some(origin) = velocity
+        .bone
+        .as_deref()
+        .and_then(|name| skeleton.find(name))
+        .and_then(|bone| global_transforms.get(bone.entity).ok())
+        .map(|global| global.translation())
+    else {
+        return;
+    };
+    if velocity.speed <= f32::EPSILON {
+        return;
+    }
+
+    let length = velocity.speed * config.angular_velocity_scale;
+    gizmos.ray(origin, velocity.axis * length, Color::YELLOW);
+
+    let radius = config.bone_axes_scale;
+    let start = velocity.axis.any_orthonormal_vector() * radius;
+    let sweep = (velocity.speed * 0.1).min(TAU);
+    let segments = 24;
+    gizmos.linestrip(
+        (0..=segments).map(|i| {
+            let angle = sweep * i as f32 / segments as f32;
+            origin + velocity.axis * length + Quat::from_axis_angle(velocity.axis, angle) * start
+        }),
+        Color::YELLOW,
+    );
+}
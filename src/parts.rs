@@ -0,0 +1,122 @@
+//! Show or hide the individual meshes the character is made of, or draw them
+//! all as wireframes.
+
+use bevy::hierarchy::HierarchyQueryExt;
+use bevy::pbr::wireframe::{Wireframe, WireframePlugin};
+use bevy::prelude::*;
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+use bevy_inspector_egui::egui;
+
+use crate::ui::keyboard_free;
+use crate::ModelRoot;
+
+/// Draws the character's triangles over its shading, to see how they stretch
+/// around the joints as it animates. Needs the renderer's
+/// `POLYGON_MODE_LINE` feature, which `main` requests.
+#[derive(Resource, Debug, Default)]
+pub struct MeshWireframe {
+    pub enabled: bool,
+}
+
+pub struct MeshPartsPlugin;
+
+impl Plugin for MeshPartsPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<WireframePlugin>() {
+            app.add_plugins(WireframePlugin);
+        }
+        app.init_resource::<MeshWireframe>().add_systems(
+            Update,
+            (
+                mesh_parts_panel,
+                (toggle_wireframe.run_if(keyboard_free), apply_wireframe).chain(),
+            ),
+        );
+    }
+}
+
+/// A mesh's own name, or its parent node's for unnamed glTF primitives.
+fn part_name(entity: Entity, names: &Query<&Name>, parents: &Query<&Parent>) -> String {
+    names
+        .get(entity)
+        .or_else(|_| {
+            parents
+                .get(entity)
+                .and_then(|parent| names.get(parent.get()))
+        })
+        .map(|name| name.as_str().to_string())
+        .unwrap_or_else(|_| format!("{entity:?}"))
+}
+
+fn mesh_parts_panel(
+    mut contexts: EguiContexts,
+    roots: Query<Entity, With<ModelRoot>>,
+    children: Query<&Children>,
+    names: Query<&Name>,
+    parents: Query<&Parent>,
+    mut meshes: Query<&mut Visibility, With<Handle<Mesh>>>,
+) {
+    let mut parts: Vec<(String, Entity)> = roots
+        .iter()
+        .flat_map(|root| children.iter_descendants(root))
+        .filter(|&entity| meshes.contains(entity))
+        .map(|entity| (part_name(entity, &names, &parents), entity))
+        .collect();
+    if parts.is_empty() {
+        return;
+    }
+    parts.sort();
+
+    egui::Window::new("Mesh parts")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            for (name, entity) in parts {
+                let Ok(mut visibility) = meshes.get_mut(entity) else {
+                    continue;
+                };
+                let mut visible = *visibility != Visibility::Hidden;
+                if ui.checkbox(&mut visible, name).changed() {
+                    *visibility = if visible {
+                        Visibility::Inherited
+                    } else {
+                        Visibility::Hidden
+                    };
+                }
+            }
+        });
+}
+
+fn toggle_wireframe(keyboard_input: Res<Input<KeyCode>>, mut wireframe: ResMut<MeshWireframe>) {
+    if keyboard_input.just_pressed(KeyCode::W) {
+        wireframe.enabled = !wireframe.enabled;
+        println!("wireframe: {}", wireframe.enabled);
+    }
+}
+
+/// Adds or removes `Wireframe` on every mesh of the character, including ones
+/// spawned after the toggle when the model reloads.
+fn apply_wireframe(
+    mut commands: Commands,
+    wireframe: Res<MeshWireframe>,
+    roots: Query<Entity, With<ModelRoot>>,
+    children: Query<&Children>,
+    meshes: Query<Option<&Wireframe>, With<Handle<Mesh>>>,
+) {
+    for entity in roots
+        .iter()
+        .flat_map(|root| children.iter_descendants(root))
+    {
+        let Ok(current) = meshes.get(entity) else {
+            continue;
+        };
+        match (wireframe.enabled, current.is_some()) {
+            (true, false) => {
+                commands.entity(entity).insert(Wireframe);
+            }
+            (false, true) => {
+                commands.entity(entity).remove::<Wireframe>();
+            }
+            _ => {}
+        }
+    }
+}
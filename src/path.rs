@@ -0,0 +1,261 @@
+//! A motion path drawn on the ground, for previewing in-place locomotion
+//! travelling along a curve rather than a straight line.
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+use bevy_inspector_egui::egui;
+
+use crate::ui::keyboard_free;
+use crate::{Animations, AnimationsMetadata, ModelConfig, ModelRoot};
+
+/// Control points of a Catmull-Rom spline on the ground plane.
+#[derive(Resource, Debug, Default)]
+pub struct MotionPath {
+    pub points: Vec<Vec3>,
+    /// How far along the path the character has travelled.
+    pub distance: f32,
+}
+
+impl MotionPath {
+    /// The spline through the control points as a polyline.
+    pub fn polyline(&self, samples_per_segment: usize) -> Vec<Vec3> {
+        let count = self.points.len();
+        if count < 2 {
+            return self.points.clone();
+        }
+        let point = |i: isize| self.points[i.clamp(0, count as isize - 1) as usize];
+        let mut line = Vec::with_capacity((count - 1) * samples_per_segment + 1);
+        for i in 0..count as isize - 1 {
+            let [p0, p1, p2, p3] = [point(i - 1), point(i), point(i + 1), point(i + 2)];
+            for sample in 0..samples_per_segment {
+                let t = sample as f32 / samples_per_segment as f32;
+                line.push(catmull_rom(p0, p1, p2, p3, t));
+            }
+        }
+        line.push(self.points[count - 1]);
+        line
+    }
+}
+
+fn catmull_rom(p0: Vec3, p1: Vec3, p2: Vec3, p3: Vec3, t: f32) -> Vec3 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    0.5 * (2.0 * p1
+        + (p2 - p0) * t
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+}
+
+/// Position and direction `distance` along a polyline, wrapping back to the
+/// start at the end.
+fn point_along(line: &[Vec3], distance: f32) -> Option<(Vec3, Vec3)> {
+    let length: f32 = line.windows(2).map(|pair| pair[0].distance(pair[1])).sum();
+    if length <= 0.0 {
+        return None;
+    }
+    let mut remaining = distance.rem_euclid(length);
+    let mut last = None;
+    for pair in line.windows(2) {
+        let segment = pair[0].distance(pair[1]);
+        if segment <= 0.0 {
+            continue;
+        }
+        last = Some((pair[1], pair[1] - pair[0]));
+        if remaining <= segment {
+            return Some((
+                pair[0].lerp(pair[1], remaining / segment),
+                pair[1] - pair[0],
+            ));
+        }
+        remaining -= segment;
+    }
+    last
+}
+
+#[derive(Resource, Debug)]
+pub struct MotionPathConfig {
+    /// Move the character along the path while the clip plays.
+    pub follow: bool,
+    /// Travel speed for clips without an authored velocity.
+    pub speed: f32,
+    pub samples_per_segment: usize,
+    /// How close to a control point a click has to be to drag it.
+    pub pick_radius: f32,
+}
+
+impl Default for MotionPathConfig {
+    fn default() -> Self {
+        Self {
+            follow: false,
+            speed: 1.4,
+            samples_per_segment: 16,
+            pick_radius: 0.2,
+        }
+    }
+}
+
+pub struct MotionPathPlugin;
+
+impl Plugin for MotionPathPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MotionPath>()
+            .init_resource::<MotionPathConfig>()
+            .add_systems(
+                Update,
+                (
+                    motion_path_panel,
+                    edit_motion_path.run_if(keyboard_free),
+                    follow_motion_path.run_if(resource_exists::<Animations>()),
+                    draw_motion_path,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// With the grave key held, right-click adds a control point where the cursor
+/// meets the ground and left-dragging moves the nearest one. Delete clears
+/// the path.
+fn edit_motion_path(
+    mut contexts: EguiContexts,
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    config: Res<MotionPathConfig>,
+    mut path: ResMut<MotionPath>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
+    mut dragging: Local<Option<usize>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Delete) {
+        path.points.clear();
+        path.distance = 0.0;
+        println!("motion path cleared");
+    }
+    if !keyboard_input.pressed(KeyCode::Grave) || mouse_input.just_released(MouseButton::Left) {
+        *dragging = None;
+        return;
+    }
+    if contexts.ctx_mut().wants_pointer_input() {
+        return;
+    }
+
+    let Some(cursor) = windows
+        .iter()
+        .next()
+        .and_then(|window| window.cursor_position())
+    else {
+        return;
+    };
+    let Some(ground) = cameras
+        .iter()
+        .find(|(camera, _)| camera.is_active)
+        .and_then(|(camera, transform)| camera.viewport_to_world(transform, cursor))
+        .and_then(|ray| Some(ray.get_point(ray.intersect_plane(Vec3::ZERO, Vec3::Y)?)))
+    else {
+        return;
+    };
+
+    if mouse_input.just_pressed(MouseButton::Right) {
+        path.points.push(ground);
+        println!("motion path: {} points", path.points.len());
+    }
+    if mouse_input.just_pressed(MouseButton::Left) {
+        *dragging = path
+            .points
+            .iter()
+            .enumerate()
+            .map(|(index, point)| (index, point.distance(ground)))
+            .filter(|&(_, distance)| distance <= config.pick_radius)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(index, _)| index);
+    }
+    if let Some(index) = *dragging {
+        if let Some(point) = path.points.get_mut(index) {
+            *point = ground;
+        }
+    }
+}
+
+fn follow_motion_path(
+    time: Res<Time>,
+    config: Res<MotionPathConfig>,
+    model_config: Res<ModelConfig>,
+    mut path: ResMut<MotionPath>,
+    animations: Res<Animations>,
+    animation_meta: Res<AnimationsMetadata>,
+    players: Query<&AnimationPlayer>,
+    mut roots: Query<(&mut Transform, &ModelRoot)>,
+    mut was_following: Local<bool>,
+) {
+    let following = config.follow && path.points.len() >= 2;
+    if !following {
+        if *was_following {
+            for (mut transform, root) in &mut roots {
+                transform.translation = root.base.translation + model_config.offset;
+                transform.rotation = root.base.rotation;
+            }
+        }
+        *was_following = false;
+        return;
+    }
+    *was_following = true;
+
+    if let Some(player) = players.iter().next().filter(|player| !player.is_paused()) {
+        let clip = player.animation_clip().id();
+        let velocity = animations
+            .0
+            .iter()
+            .position(|handle| handle.id() == clip)
+            .and_then(|index| animation_meta.0.get(index))
+            .and_then(|params| params.authored_velocity)
+            .unwrap_or(config.speed);
+        path.distance += velocity * player.speed() * time.delta_seconds();
+    }
+
+    let line = path.polyline(config.samples_per_segment);
+    let Some((position, direction)) = point_along(&line, path.distance) else {
+        return;
+    };
+    let heading = Vec3::new(direction.x, 0.0, direction.z).normalize_or_zero();
+    for (mut transform, _) in &mut roots {
+        transform.translation = position + model_config.offset;
+        if heading != Vec3::ZERO {
+            transform.rotation = Quat::from_rotation_arc(Vec3::Z, heading);
+        }
+    }
+}
+
+fn draw_motion_path(mut gizmos: Gizmos, config: Res<MotionPathConfig>, path: Res<MotionPath>) {
+    for point in &path.points {
+        gizmos.sphere(*point, Quat::IDENTITY, 0.05, Color::ORANGE);
+    }
+    gizmos.linestrip(path.polyline(config.samples_per_segment), Color::YELLOW);
+}
+
+fn motion_path_panel(
+    mut contexts: EguiContexts,
+    mut config: ResMut<MotionPathConfig>,
+    mut path: ResMut<MotionPath>,
+) {
+    egui::Window::new("Motion path")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label("hold ` and right-click to add points, drag to move them");
+            ui.label(format!("{} control points", path.points.len()));
+            ui.checkbox(&mut config.follow, "follow path");
+            ui.add(
+                egui::Slider::new(&mut config.speed, 0.0..=10.0)
+                    .text("speed without authored velocity"),
+            );
+            ui.horizontal(|ui| {
+                if ui.button("restart").clicked() {
+                    path.distance = 0.0;
+                }
+                if ui.button("clear (Delete)").clicked() {
+                    path.points.clear();
+                    path.distance = 0.0;
+                }
+            });
+        });
+}
@@ -0,0 +1,1533 @@
+//! Playback modes layered on top of the basic play/pause/seek controls.
+
+use bevy::animation::{animation_player, RepeatAnimation};
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+use bevy::utils::{HashMap, HashSet};
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+use bevy_inspector_egui::egui;
+
+use crate::poses::LivePoseBlend;
+use crate::sampling::{key_times, mirror_pose, sample_pose};
+use crate::skeleton::Skeleton;
+use crate::ui::{keyboard_free, UiSettings};
+use crate::{play_animation, Animations, AnimationsMetadata, CurrentAnimation};
+
+/// Raises playback speed each time the clip loops, to find the speed at which
+/// a motion stops reading well.
+#[derive(Resource, Debug)]
+pub struct LoopSpeedRampConfig {
+    /// Fraction of the starting speed added per completed loop.
+    pub increment: f32,
+    /// Speed never ramps beyond this multiple of the starting speed.
+    pub cap: f32,
+}
+
+impl Default for LoopSpeedRampConfig {
+    fn default() -> Self {
+        Self {
+            increment: 0.1,
+            cap: 3.0,
+        }
+    }
+}
+
+#[derive(Resource, Debug)]
+struct LoopSpeedRamp {
+    base_speed: f32,
+    start_completions: u32,
+    last_completions: u32,
+}
+
+/// Plays the clip for a possibly fractional number of loops from the start,
+/// then holds the pose it stopped on.
+#[derive(Resource, Debug)]
+pub struct FractionalLoopConfig {
+    pub loops: f32,
+}
+
+impl Default for FractionalLoopConfig {
+    fn default() -> Self {
+        Self { loops: 2.5 }
+    }
+}
+
+#[derive(Resource, Debug)]
+struct FractionalLoopRun {
+    loops: f32,
+    /// Clip the run was started on; switching clips cancels it.
+    clip: AssetId<AnimationClip>,
+}
+
+/// Multiplies playback speed while `F` is held, like a video player's
+/// fast-forward.
+#[derive(Resource, Debug)]
+pub struct FastForwardConfig {
+    pub multiplier: f32,
+}
+
+impl Default for FastForwardConfig {
+    fn default() -> Self {
+        Self { multiplier: 4.0 }
+    }
+}
+
+/// Speed to restore when the fast-forward key is released.
+#[derive(Resource, Debug)]
+struct FastForward {
+    previous_speed: f32,
+}
+
+/// What happens when the playhead reaches the end of the clip.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LoopMode {
+    /// Play to the end, then pause on the last pose. Playing again restarts
+    /// from the first frame.
+    Once,
+    #[default]
+    Loop,
+    /// Reverse direction at each end of the clip.
+    PingPong,
+}
+
+impl LoopMode {
+    fn next(self) -> Self {
+        match self {
+            LoopMode::Once => LoopMode::Loop,
+            LoopMode::Loop => LoopMode::PingPong,
+            LoopMode::PingPong => LoopMode::Once,
+        }
+    }
+
+    /// How a player repeats its clip in this mode.
+    pub fn repeat(self) -> RepeatAnimation {
+        match self {
+            LoopMode::Once => RepeatAnimation::Never,
+            LoopMode::Loop | LoopMode::PingPong => RepeatAnimation::Forever,
+        }
+    }
+}
+
+/// Part of the clip that playback loops within, to iterate on a sub-loop.
+/// `None` means the clip's own start or end.
+#[derive(Resource, Debug, Default)]
+pub struct LoopRange {
+    pub in_point: Option<f32>,
+    pub out_point: Option<f32>,
+    /// Seconds before the out point over which the pose blends into the one
+    /// after the in point, so the seam doesn't pop; 0 cuts straight back.
+    /// Only applies playing forward.
+    pub crossfade: f32,
+}
+
+impl LoopRange {
+    pub fn is_set(&self) -> bool {
+        self.in_point.is_some() || self.out_point.is_some()
+    }
+
+    /// Drops the in and out points, keeping the crossfade for the next range.
+    pub fn clear(&mut self) {
+        self.in_point = None;
+        self.out_point = None;
+    }
+
+    /// The crossfade for a range from `start` to `end`, at most half of it.
+    pub fn crossfade_within(&self, start: f32, end: f32) -> f32 {
+        self.crossfade.clamp(0.0, (end - start).max(0.0) * 0.5)
+    }
+
+    /// The range in seconds for a clip lasting `duration`.
+    pub fn bounds(&self, duration: f32) -> (f32, f32) {
+        (
+            self.in_point.unwrap_or(0.0),
+            self.out_point.unwrap_or(duration),
+        )
+    }
+}
+
+/// Part of the clip the timeline shows, zoomed in to scrub long clips finer
+/// than the slider's width allows across the whole clip.
+#[derive(Resource, Debug)]
+pub struct TimelineView {
+    /// 1 shows the whole clip, 2 half of it, and so on.
+    pub zoom: f32,
+    /// Time at the left edge, in seconds.
+    pub start: f32,
+}
+
+impl Default for TimelineView {
+    fn default() -> Self {
+        Self {
+            zoom: 1.0,
+            start: 0.0,
+        }
+    }
+}
+
+impl TimelineView {
+    /// The visible `(start, end)` of a clip lasting `duration`.
+    pub fn window(&self, duration: f32) -> (f32, f32) {
+        let length = duration / self.zoom.max(1.0);
+        let start = self.start.clamp(0.0, duration - length);
+        (start, start + length)
+    }
+}
+
+/// Frame rate assumed when stepping a paused clip one frame at a time.
+#[derive(Resource, Debug)]
+pub struct FrameStepConfig {
+    pub fps: f32,
+}
+
+impl Default for FrameStepConfig {
+    fn default() -> Self {
+        Self { fps: 30.0 }
+    }
+}
+
+impl FrameStepConfig {
+    /// Index of the frame shown at `time`.
+    pub fn frame_at(&self, time: f32) -> i64 {
+        (time * self.fps).round() as i64
+    }
+
+    /// The first frame boundary strictly after `time`.
+    pub fn next_frame(&self, time: f32) -> i64 {
+        (time * self.fps + 1e-3).floor() as i64 + 1
+    }
+
+    /// The last frame boundary strictly before `time`.
+    pub fn previous_frame(&self, time: f32) -> i64 {
+        (time * self.fps - 1e-3).ceil() as i64 - 1
+    }
+
+    /// Time of `frame`, clamped to the clip.
+    pub fn frame_time(&self, frame: i64, duration: f32) -> f32 {
+        (frame as f32 / self.fps).clamp(0.0, duration)
+    }
+}
+
+/// Range the A / Z keys keep the magnitude of the playback speed in, so it
+/// can't reach zero or flip sign by accident. R reverses on purpose.
+#[derive(Resource, Debug)]
+pub struct SpeedLimits {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl Default for SpeedLimits {
+    fn default() -> Self {
+        Self { min: 0.1, max: 4.0 }
+    }
+}
+
+impl SpeedLimits {
+    /// `speed` with `step` added to its magnitude, clamped to the range and
+    /// keeping its sign.
+    pub fn adjust(&self, speed: f32, step: f32) -> f32 {
+        self.set(speed, speed.abs() + step)
+    }
+
+    /// `magnitude` clamped to the range, in the direction of `speed`.
+    pub fn set(&self, speed: f32, magnitude: f32) -> f32 {
+        let magnitude = magnitude.clamp(self.min, self.max);
+        if speed < 0.0 {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+}
+
+/// Eases speed changes made with A / Z over `duration` instead of jumping to
+/// the new speed.
+#[derive(Resource, Debug)]
+pub struct SmoothSpeed {
+    /// Seconds to reach a new speed; 0 applies it at once.
+    pub duration: f32,
+    from: f32,
+    target: Option<f32>,
+    elapsed: f32,
+}
+
+impl Default for SmoothSpeed {
+    fn default() -> Self {
+        Self {
+            duration: 0.2,
+            from: 1.0,
+            target: None,
+            elapsed: 0.0,
+        }
+    }
+}
+
+impl SmoothSpeed {
+    /// Starts easing from `current` towards `target`.
+    pub fn ease_to(&mut self, current: f32, target: f32) {
+        self.from = current;
+        self.target = Some(target);
+        self.elapsed = 0.0;
+    }
+
+    /// The speed being eased towards, or `current` when there's none.
+    pub fn target_or(&self, current: f32) -> f32 {
+        self.target.unwrap_or(current)
+    }
+
+    pub fn cancel(&mut self) {
+        self.target = None;
+    }
+}
+
+/// Times bookmarked with the number keys, per animation name, to return to
+/// the same poses while comparing. Kept until the app exits.
+#[derive(Resource, Default, Debug)]
+pub struct Bookmarks(pub HashMap<String, [Option<f32>; 9]>);
+
+/// Holds each keyframed pose until the next key instead of interpolating, to
+/// review the key poses without the in-betweens.
+#[derive(Resource, Default, Debug)]
+pub struct SteppedPreview {
+    pub enabled: bool,
+}
+
+/// Plays the clip mirrored left to right, e.g. to preview a left-handed
+/// version of a clip authored right-handed.
+#[derive(Resource, Default, Debug)]
+pub struct MirroredPlayback {
+    pub enabled: bool,
+}
+
+/// Global slow motion on top of the clips' own speeds, for a close look
+/// without touching the speeds tuned per clip. It scales the app's virtual
+/// clock, so everything driven by it, not just the players, slows down.
+#[derive(Resource, Debug)]
+pub struct SlowMotion {
+    pub scale: f32,
+}
+
+impl Default for SlowMotion {
+    fn default() -> Self {
+        Self { scale: 1.0 }
+    }
+}
+
+/// Scales G cycles through.
+pub const SLOW_MOTION_PRESETS: [f32; 4] = [1.0, 0.5, 0.25, 0.1];
+
+impl SlowMotion {
+    /// The preset after the current scale, wrapping back to full speed.
+    pub fn next_preset(&self) -> f32 {
+        SLOW_MOTION_PRESETS
+            .iter()
+            .position(|&scale| scale == self.scale)
+            .map_or(SLOW_MOTION_PRESETS[0], |index| {
+                SLOW_MOTION_PRESETS[(index + 1) % SLOW_MOTION_PRESETS.len()]
+            })
+    }
+}
+
+/// Keeps every animation player at the first player's phase, for rigs split
+/// into parts that each have their own player. Speed and pausing follow the
+/// first player too, so scrubbing it scrubs them all.
+#[derive(Resource, Default, Debug)]
+pub struct MasterClock {
+    pub enabled: bool,
+}
+
+/// Scales playback of locomotion clips with a simulated character velocity,
+/// the way games speed up a run cycle as the character moves faster. Only
+/// clips with an `authored_velocity` in their params are warped. The warped
+/// speed is eased to like an A / Z change whenever the velocity or the clip
+/// changes, so the speed keys still work on top of it, and switching the warp
+/// off eases back to the clip's own speed.
+#[derive(Resource, Debug)]
+pub struct SpeedWarpConfig {
+    pub enabled: bool,
+    /// Simulated character velocity in meters per second.
+    pub velocity: f32,
+    pub min_speed: f32,
+    pub max_speed: f32,
+}
+
+impl Default for SpeedWarpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            velocity: 1.4,
+            min_speed: 0.25,
+            max_speed: 3.0,
+        }
+    }
+}
+
+impl SpeedWarpConfig {
+    /// Playback speed that makes a clip authored at `authored_velocity` move
+    /// at the simulated velocity.
+    pub fn speed_for(&self, authored_velocity: f32) -> f32 {
+        (self.velocity / authored_velocity.max(f32::EPSILON)).clamp(self.min_speed, self.max_speed)
+    }
+}
+
+/// Advances to the next clip every `interval` seconds of wall-clock time,
+/// whether or not the current clip has finished a loop, for an unattended
+/// display of the library. Any other key press stops it, to take over and
+/// inspect the clip on screen.
+#[derive(Resource, Debug)]
+pub struct SlideshowConfig {
+    pub enabled: bool,
+    pub interval: f32,
+    /// Names of clips the slideshow passes over, e.g. the T-pose and other
+    /// utility clips.
+    pub skipped: HashSet<String>,
+}
+
+impl Default for SlideshowConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval: 3.0,
+            skipped: HashSet::from_iter(["TPose".to_string()]),
+        }
+    }
+}
+
+impl SlideshowConfig {
+    /// Index of the first clip after `current` that takes part in the
+    /// slideshow, wrapping around; `None` if there are no clips or all of
+    /// them are skipped.
+    pub fn next_clip(&self, animation_meta: &AnimationsMetadata, current: usize) -> Option<usize> {
+        let count = animation_meta.0.len();
+        (1..=count)
+            .map(|step| (current + step) % count)
+            .find(|&index| !self.skipped.contains(&animation_meta.0[index].name))
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+struct SlideshowTimer(Timer);
+
+const MARKER_HEIGHT: f32 = 12.0;
+/// How close in pixels a click has to be to a timeline marker to seek to it.
+const MARKER_PICK_DISTANCE: f32 = 6.0;
+
+pub struct PlaybackPlugin;
+
+impl Plugin for PlaybackPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LoopSpeedRampConfig>()
+            .init_resource::<FractionalLoopConfig>()
+            .init_resource::<SteppedPreview>()
+            .init_resource::<MirroredPlayback>()
+            .init_resource::<MasterClock>()
+            .init_resource::<SlowMotion>()
+            .init_resource::<FrameStepConfig>()
+            .init_resource::<SpeedLimits>()
+            .init_resource::<SmoothSpeed>()
+            .init_resource::<LoopMode>()
+            .init_resource::<LoopRange>()
+            .init_resource::<TimelineView>()
+            .init_resource::<Bookmarks>()
+            .init_resource::<FastForwardConfig>()
+            .init_resource::<SpeedWarpConfig>()
+            .init_resource::<SlideshowConfig>()
+            .init_resource::<SlideshowTimer>()
+            .add_systems(
+                Update,
+                (
+                    (
+                        toggle_loop_speed_ramp.run_if(keyboard_free),
+                        ramp_speed_on_loop.run_if(resource_exists::<LoopSpeedRamp>()),
+                    )
+                        .chain(),
+                    (
+                        fractional_loop_panel,
+                        stop_after_fractional_loops.run_if(resource_exists::<FractionalLoopRun>()),
+                    )
+                        .chain(),
+                    toggle_stepped_preview.run_if(keyboard_free),
+                    toggle_mirrored_playback.run_if(keyboard_free),
+                    toggle_master_clock.run_if(keyboard_free),
+                    (
+                        toggle_slow_motion.run_if(keyboard_free),
+                        apply_slow_motion.run_if(resource_changed::<SlowMotion>()),
+                    )
+                        .chain(),
+                    (
+                        speed_panel.run_if(resource_exists::<Animations>()),
+                        ease_playback_speed
+                            .run_if(|smooth: Res<SmoothSpeed>| smooth.target.is_some()),
+                    )
+                        .chain(),
+                    fast_forward_while_held.run_if(keyboard_free),
+                    (
+                        speed_warp_panel,
+                        warp_speed_to_velocity.run_if(resource_exists::<Animations>()),
+                    )
+                        .chain(),
+                    timeline_panel,
+                    bookmark_keys.run_if(keyboard_free),
+                    (
+                        toggle_loop_mode.run_if(keyboard_free),
+                        hold_end_pose.run_if(|mode: Res<LoopMode>| *mode == LoopMode::Once),
+                    )
+                        .chain(),
+                    (
+                        set_loop_range.run_if(keyboard_free),
+                        wrap_to_loop_range.run_if(|range: Res<LoopRange>| range.is_set()),
+                    )
+                        .chain(),
+                    (
+                        slideshow_panel,
+                        advance_slideshow
+                            .run_if(|config: Res<SlideshowConfig>| config.enabled)
+                            .run_if(resource_exists::<Animations>()),
+                    )
+                        .chain(),
+                ),
+            )
+            .add_systems(
+                PostUpdate,
+                (
+                    apply_loop_mode.run_if(not(resource_exists::<FractionalLoopRun>())),
+                    reverse_at_clip_ends.run_if(|mode: Res<LoopMode>| *mode == LoopMode::PingPong),
+                    follow_master_clock.run_if(|clock: Res<MasterClock>| clock.enabled),
+                )
+                    .chain()
+                    .before(animation_player),
+            )
+            .add_systems(
+                PostUpdate,
+                (
+                    crossfade_loop_seam
+                        .run_if(|range: Res<LoopRange>| range.is_set() && range.crossfade > 0.0),
+                    hold_key_poses.run_if(|preview: Res<SteppedPreview>| preview.enabled),
+                    mirror_playback.run_if(|mirrored: Res<MirroredPlayback>| mirrored.enabled),
+                )
+                    .chain()
+                    .after(animation_player)
+                    .before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+/// Moves the speed along a smoothstep from where the change started to the
+/// target, which lands exactly on the target without overshooting it.
+fn ease_playback_speed(
+    time: Res<Time>,
+    mut smooth: ResMut<SmoothSpeed>,
+    mut players: Query<&mut AnimationPlayer>,
+) {
+    let Some(target) = smooth.target else {
+        return;
+    };
+    smooth.elapsed += time.delta_seconds();
+    let t = if smooth.duration > 0.0 {
+        (smooth.elapsed / smooth.duration).min(1.0)
+    } else {
+        1.0
+    };
+    let speed = if t < 1.0 {
+        smooth.from + (target - smooth.from) * t * t * (3.0 - 2.0 * t)
+    } else {
+        smooth.target = None;
+        target
+    };
+    for mut player in &mut players {
+        player.set_speed(speed);
+    }
+}
+
+fn toggle_loop_speed_ramp(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    ramp: Option<Res<LoopSpeedRamp>>,
+    mut players: Query<&mut AnimationPlayer>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::E) {
+        return;
+    }
+    match ramp {
+        Some(ramp) => {
+            for mut player in &mut players {
+                player.set_speed(ramp.base_speed);
+            }
+            commands.remove_resource::<LoopSpeedRamp>();
+            println!("loop speed ramp: off (speed {})", ramp.base_speed);
+        }
+        None => {
+            let Some(player) = players.iter().next() else {
+                return;
+            };
+            commands.insert_resource(LoopSpeedRamp {
+                base_speed: player.speed(),
+                start_completions: player.completions(),
+                last_completions: player.completions(),
+            });
+            println!("loop speed ramp: on (from speed {})", player.speed());
+        }
+    }
+}
+
+fn ramp_speed_on_loop(
+    config: Res<LoopSpeedRampConfig>,
+    mut ramp: ResMut<LoopSpeedRamp>,
+    mut players: Query<&mut AnimationPlayer>,
+) {
+    let Some(completions) = players.iter().next().map(|player| player.completions()) else {
+        return;
+    };
+    if completions == ramp.last_completions {
+        return;
+    }
+    // Switching clips resets the completion count.
+    if completions < ramp.start_completions {
+        ramp.start_completions = 0;
+    }
+    ramp.last_completions = completions;
+
+    let loops = (completions - ramp.start_completions) as f32;
+    let factor = (1.0 + config.increment * loops).min(config.cap);
+    let speed = ramp.base_speed * factor;
+    for mut player in &mut players {
+        player.set_speed(speed);
+    }
+    println!("loop {loops}: playback speed {speed:.2} ({factor:.2}x)");
+}
+
+fn fractional_loop_panel(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut config: ResMut<FractionalLoopConfig>,
+    mut players: Query<&mut AnimationPlayer>,
+) {
+    let mut start =
+        keyboard_input.just_pressed(KeyCode::F6) && !contexts.ctx_mut().wants_keyboard_input();
+    egui::Window::new("Fractional loops")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::DragValue::new(&mut config.loops)
+                        .speed(0.05)
+                        .clamp_range(0.05..=100.0),
+                );
+                ui.label("loops");
+                start |= ui.button("play (F6)").clicked();
+            });
+        });
+    if !start {
+        return;
+    }
+
+    let Some(clip) = players
+        .iter()
+        .next()
+        .map(|player| player.animation_clip().id())
+    else {
+        return;
+    };
+    for mut player in &mut players {
+        player
+            .set_repeat(RepeatAnimation::Count(config.loops.ceil() as u32))
+            .replay();
+        player.resume();
+    }
+    commands.insert_resource(FractionalLoopRun {
+        loops: config.loops,
+        clip,
+    });
+    println!("playing {} loops", config.loops);
+}
+
+/// Holds the pose once the run's loops have played, handing the player back
+/// to the loop mode so that resuming plays on.
+fn stop_after_fractional_loops(
+    mut commands: Commands,
+    run: Res<FractionalLoopRun>,
+    mode: Res<LoopMode>,
+    clips: Res<Assets<AnimationClip>>,
+    mut players: Query<&mut AnimationPlayer>,
+) {
+    if players
+        .iter()
+        .next()
+        .is_some_and(|player| player.animation_clip().id() != run.clip)
+    {
+        commands.remove_resource::<FractionalLoopRun>();
+        println!("fractional loops: stopped, the clip changed");
+        return;
+    }
+    let Some((duration, progress)) = players.iter().next().and_then(|player| {
+        let duration = clips.get(player.animation_clip())?.duration();
+        let progress = player.completions() as f32 * duration + player.seek_time().min(duration);
+        Some((duration, progress))
+    }) else {
+        return;
+    };
+    let target = run.loops * duration;
+    if progress < target {
+        return;
+    }
+
+    let fraction = run.loops.fract();
+    let hold_at = if fraction > 0.0 {
+        fraction * duration
+    } else {
+        duration
+    };
+    for mut player in &mut players {
+        // Replaying clears the completions, which would otherwise leave the
+        // player finished under its old count or the loop mode's.
+        player.set_repeat(mode.repeat()).replay();
+        player.seek_to(hold_at);
+        player.pause();
+    }
+    commands.remove_resource::<FractionalLoopRun>();
+    println!("held after {} loops at {hold_at:.3}s", run.loops);
+}
+
+fn toggle_stepped_preview(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut preview: ResMut<SteppedPreview>,
+    clips: Res<Assets<AnimationClip>>,
+    players: Query<&AnimationPlayer>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::Y) {
+        return;
+    }
+    preview.enabled = !preview.enabled;
+    let key_poses = players
+        .iter()
+        .next()
+        .and_then(|player| clips.get(player.animation_clip()))
+        .map(|clip| key_times(clip).len())
+        .unwrap_or(0);
+    println!(
+        "stepped preview: {} ({key_poses} key poses)",
+        preview.enabled
+    );
+}
+
+/// Replaces the sampled pose with the one at the latest keyframe at or before
+/// the playhead.
+fn hold_key_poses(
+    skeleton: Res<Skeleton>,
+    clips: Res<Assets<AnimationClip>>,
+    players: Query<&AnimationPlayer>,
+    mut transforms: Query<&mut Transform>,
+) {
+    let Some(player) = players.iter().next() else {
+        return;
+    };
+    let Some(clip) = clips.get(player.animation_clip()) else {
+        return;
+    };
+    let times = key_times(clip);
+    let time = player.seek_time();
+    let Some(&key) = times
+        .iter()
+        .rev()
+        .find(|&&key| key <= time)
+        .or(times.first())
+    else {
+        return;
+    };
+
+    let pose = sample_pose(clip, &skeleton, key);
+    for (bone, local) in skeleton.bones.iter().zip(pose) {
+        if let Ok(mut transform) = transforms.get_mut(bone.entity) {
+            *transform = local;
+        }
+    }
+}
+
+fn toggle_mirrored_playback(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut mirrored: ResMut<MirroredPlayback>,
+) {
+    if keyboard_input.just_pressed(KeyCode::X) {
+        mirrored.enabled = !mirrored.enabled;
+        println!("mirrored playback: {}", mirrored.enabled);
+    }
+}
+
+fn toggle_master_clock(keyboard_input: Res<Input<KeyCode>>, mut clock: ResMut<MasterClock>) {
+    if keyboard_input.just_pressed(KeyCode::Tab) {
+        clock.enabled = !clock.enabled;
+        println!("master clock: {}", clock.enabled);
+    }
+}
+
+fn toggle_slow_motion(keyboard_input: Res<Input<KeyCode>>, mut slow_motion: ResMut<SlowMotion>) {
+    if keyboard_input.just_pressed(KeyCode::G) {
+        slow_motion.scale = slow_motion.next_preset();
+        println!("slow motion: {}x", slow_motion.scale);
+    }
+}
+
+fn apply_slow_motion(slow_motion: Res<SlowMotion>, mut time: ResMut<Time<Virtual>>) {
+    time.set_relative_speed(slow_motion.scale);
+}
+
+/// Runs before the players advance, so they all step from the same phase.
+/// Clips of different lengths are matched by normalized time.
+fn follow_master_clock(
+    clips: Res<Assets<AnimationClip>>,
+    mut players: Query<&mut AnimationPlayer>,
+) {
+    let duration = |player: &AnimationPlayer| {
+        clips
+            .get(player.animation_clip())
+            .map(|clip| clip.duration().max(f32::EPSILON))
+    };
+    let Some((phase, speed, paused)) = players.iter().next().and_then(|leader| {
+        Some((
+            leader.seek_time() / duration(leader)?,
+            leader.speed(),
+            leader.is_paused(),
+        ))
+    }) else {
+        return;
+    };
+    for mut player in players.iter_mut().skip(1) {
+        let Some(duration) = duration(&player) else {
+            continue;
+        };
+        player.seek_to(phase * duration);
+        if player.speed() != speed {
+            player.set_speed(speed);
+        }
+        match (paused, player.is_paused()) {
+            (true, false) => player.pause(),
+            (false, true) => player.resume(),
+            _ => {}
+        }
+    }
+}
+
+/// Swaps the pose the player (or the key pose hold) just applied for its
+/// mirror image. Switching off needs no cleanup; the player overwrites the
+/// bones again next frame.
+fn mirror_playback(skeleton: Res<Skeleton>, mut transforms: Query<&mut Transform>) {
+    let pose: Vec<Transform> = skeleton
+        .bones
+        .iter()
+        .map(|bone| transforms.get(bone.entity).copied().unwrap_or(bone.rest))
+        .collect();
+    for (bone, local) in skeleton.bones.iter().zip(mirror_pose(&skeleton, &pose)) {
+        if let Ok(mut transform) = transforms.get_mut(bone.entity) {
+            *transform = local;
+        }
+    }
+}
+
+fn fast_forward_while_held(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    config: Res<FastForwardConfig>,
+    fast_forward: Option<Res<FastForward>>,
+    mut players: Query<&mut AnimationPlayer>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F) && fast_forward.is_none() {
+        let Some(previous_speed) = players.iter().next().map(|player| player.speed()) else {
+            return;
+        };
+        for mut player in &mut players {
+            player.set_speed(previous_speed * config.multiplier);
+        }
+        commands.insert_resource(FastForward { previous_speed });
+    } else if keyboard_input.just_released(KeyCode::F) {
+        let Some(fast_forward) = fast_forward else {
+            return;
+        };
+        for mut player in &mut players {
+            player.set_speed(fast_forward.previous_speed);
+        }
+        commands.remove_resource::<FastForward>();
+    }
+}
+
+/// Exact speed entry, for getting back to a round value after nudging it
+/// with A / Z. Like those keys it sets the clip's speed in its params too.
+fn speed_panel(
+    mut contexts: EguiContexts,
+    limits: Res<SpeedLimits>,
+    current_animation: Res<CurrentAnimation>,
+    mut animation_meta: ResMut<AnimationsMetadata>,
+    mut smooth: ResMut<SmoothSpeed>,
+    mut players: Query<&mut AnimationPlayer>,
+) {
+    let Some(current) = players.iter().next().map(|player| player.speed()) else {
+        return;
+    };
+    let mut magnitude = smooth.target_or(current).abs();
+    let mut changed = false;
+    egui::Window::new("Speed")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut magnitude)
+                            .speed(0.01)
+                            .clamp_range(limits.min..=limits.max)
+                            .suffix("x"),
+                    )
+                    .changed();
+                ui.label(format!("{:.0}%", magnitude * 100.0));
+                if ui.button("reset to 1.0x").clicked() {
+                    magnitude = 1.0;
+                    changed = true;
+                }
+            });
+        });
+    if !changed {
+        return;
+    }
+
+    let speed = limits.set(current, magnitude);
+    smooth.cancel();
+    for mut player in &mut players {
+        player.set_speed(speed);
+    }
+    if let Some(params) = animation_meta.0.get_mut(current_animation.0) {
+        params.playback_speed = speed.abs();
+    }
+    println!("playback speed: {speed}");
+}
+
+fn speed_warp_panel(mut contexts: EguiContexts, mut config: ResMut<SpeedWarpConfig>) {
+    egui::Window::new("Speed warp")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.checkbox(&mut config.enabled, "warp locomotion to velocity");
+            ui.add(egui::Slider::new(&mut config.velocity, 0.0..=10.0).text("velocity (m/s)"));
+        });
+}
+
+/// `warped` is the clip and warped speed last eased to, so the warp only
+/// steps in when one of them changes.
+fn warp_speed_to_velocity(
+    config: Res<SpeedWarpConfig>,
+    limits: Res<SpeedLimits>,
+    animations: Res<Animations>,
+    animation_meta: Res<AnimationsMetadata>,
+    mut smooth: ResMut<SmoothSpeed>,
+    players: Query<&AnimationPlayer>,
+    mut warped: Local<Option<(AssetId<AnimationClip>, f32)>>,
+) {
+    let Some(player) = players.iter().next() else {
+        return;
+    };
+    let clip = player.animation_clip().id();
+    let params = animations
+        .0
+        .iter()
+        .position(|handle| handle.id() == clip)
+        .and_then(|index| animation_meta.0.get(index));
+
+    if !config.enabled {
+        if warped.take().is_some() {
+            if let Some(params) = params {
+                let speed = params.playback_speed.copysign(player.speed());
+                smooth.ease_to(player.speed(), speed);
+                println!("speed warp: off, back to {speed:.2}x");
+            }
+        }
+        return;
+    }
+
+    let Some(authored_velocity) = params.and_then(|params| params.authored_velocity) else {
+        return;
+    };
+    let speed = limits.set(player.speed(), config.speed_for(authored_velocity));
+    if *warped != Some((clip, speed)) {
+        *warped = Some((clip, speed));
+        smooth.ease_to(player.speed(), speed);
+    }
+}
+
+fn slideshow_panel(
+    mut contexts: EguiContexts,
+    keyboard_input: Res<Input<KeyCode>>,
+    animation_meta: Res<AnimationsMetadata>,
+    mut config: ResMut<SlideshowConfig>,
+    mut timer: ResMut<SlideshowTimer>,
+) {
+    let was_enabled = config.enabled;
+    // Typing in a text field is neither F7 nor manual control.
+    let keyboard_input = if contexts.ctx_mut().wants_keyboard_input() {
+        Input::default()
+    } else {
+        keyboard_input.clone()
+    };
+    if keyboard_input.any_just_pressed([KeyCode::F7, KeyCode::M]) {
+        config.enabled = !config.enabled;
+        println!("slideshow: {} (every {}s)", config.enabled, config.interval);
+    } else if config.enabled
+        && keyboard_input
+            .get_just_pressed()
+            .any(|key| !matches!(key, KeyCode::ShiftLeft | KeyCode::ShiftRight))
+    {
+        config.enabled = false;
+        println!("slideshow: stopped by manual control");
+    }
+    egui::Window::new("Slideshow")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.checkbox(&mut config.enabled, "advance on a timer (F7 / M)");
+            ui.add(
+                egui::Slider::new(&mut config.interval, 0.5..=60.0)
+                    .text("seconds per clip")
+                    .logarithmic(true),
+            );
+            ui.collapsing("clips", |ui| {
+                for params in &animation_meta.0 {
+                    let mut included = !config.skipped.contains(&params.name);
+                    if ui.checkbox(&mut included, params.name.as_str()).changed() {
+                        if included {
+                            config.skipped.remove(&params.name);
+                        } else {
+                            config.skipped.insert(params.name.clone());
+                        }
+                    }
+                }
+            });
+        });
+    let interval = Duration::from_secs_f32(config.interval);
+    if config.enabled && (!was_enabled || timer.0.duration() != interval) {
+        timer.0 = Timer::from_seconds(config.interval, TimerMode::Repeating);
+    }
+}
+
+fn advance_slideshow(
+    time: Res<Time<Real>>,
+    config: Res<SlideshowConfig>,
+    mut timer: ResMut<SlideshowTimer>,
+    animations: Res<Animations>,
+    animation_meta: Res<AnimationsMetadata>,
+    live_pose_blend: Res<LivePoseBlend>,
+    mut current_animation: ResMut<CurrentAnimation>,
+    mut players: Query<&mut AnimationPlayer>,
+) {
+    if !timer.0.tick(time.delta()).just_finished() {
+        return;
+    }
+    let Some((next, clip)) = config
+        .next_clip(&animation_meta, current_animation.0)
+        .and_then(|next| Some((next, animations.0.get(next)?)))
+    else {
+        return;
+    };
+    current_animation.0 = next;
+    for mut player in &mut players {
+        play_animation(&mut player, clip, &live_pose_blend);
+    }
+    println!("slideshow: {}", animation_meta.0[next].name);
+}
+
+/// Scrubber for the playing clip, with a tick under it at every keyframe and
+/// a colored one at every event; clicking a tick seeks to it.
+fn timeline_panel(
+    mut contexts: EguiContexts,
+    clips: Res<Assets<AnimationClip>>,
+    animations: Option<Res<Animations>>,
+    animation_meta: Res<AnimationsMetadata>,
+    frame_step: Res<FrameStepConfig>,
+    mut loop_range: ResMut<LoopRange>,
+    mut view: ResMut<TimelineView>,
+    ui_settings: Res<UiSettings>,
+    mut players: Query<&mut AnimationPlayer>,
+    mut resume_after_drag: Local<Option<bool>>,
+    mut target_frame: Local<i64>,
+) {
+    let Some(mut player) = players.iter_mut().next() else {
+        return;
+    };
+    let Some(clip) = clips.get(player.animation_clip()) else {
+        return;
+    };
+    let duration = clip.duration();
+    let keys = key_times(clip);
+    let events = animations
+        .and_then(|animations| {
+            let clip = player.animation_clip().id();
+            animations.0.iter().position(|handle| handle.id() == clip)
+        })
+        .and_then(|index| animation_meta.0.get(index))
+        .map_or(&[][..], |params| params.events.as_slice());
+    let mut time = player.seek_time();
+    // Keep the playhead in view while playing.
+    let (view_start, view_end) = view.window(duration);
+    if !player.is_paused() && !(view_start..=view_end).contains(&time) {
+        view.start = time;
+    }
+    let (view_start, view_end) = view.window(duration);
+    let mut zoom = view.zoom;
+    let mut pan = view_start;
+    let mut crossfade = loop_range.crossfade;
+    let mut dragging = false;
+    let mut changed = false;
+    let (align, offset) = ui_settings.dock.anchor();
+    let window = egui::Window::new("Timeline").anchor(align, offset);
+    window.show(contexts.ctx_mut(), |ui| {
+        let response = ui.add(
+            egui::Slider::new(&mut time, view_start..=view_end)
+                .show_value(false)
+                .clamp_to_range(true),
+        );
+        dragging = response.dragged();
+        changed = response.changed();
+        ui.horizontal(|ui| {
+            ui.label("zoom");
+            ui.add(
+                egui::DragValue::new(&mut zoom)
+                    .speed(0.05)
+                    .clamp_range(1.0..=64.0)
+                    .suffix("x"),
+            );
+            if zoom > 1.0 {
+                ui.add(
+                    egui::Slider::new(&mut pan, 0.0..=duration - (view_end - view_start))
+                        .show_value(false)
+                        .text(format!("{view_start:.2}s - {view_end:.2}s")),
+                );
+            }
+        });
+
+        let (rect, response) = ui.allocate_exact_size(
+            egui::vec2(ui.spacing().slider_width, MARKER_HEIGHT),
+            egui::Sense::click(),
+        );
+        let x = |time: f32| {
+            rect.left()
+                + (time - view_start) / (view_end - view_start).max(f32::EPSILON) * rect.width()
+        };
+        let painter = ui.painter_at(rect);
+        for &key in &keys {
+            painter.line_segment(
+                [
+                    egui::pos2(x(key), rect.center().y),
+                    egui::pos2(x(key), rect.bottom()),
+                ],
+                egui::Stroke::new(1.0, egui::Color32::LIGHT_BLUE),
+            );
+        }
+        for event in events {
+            let [r, g, b, a] = event.kind.color().as_rgba_u8();
+            painter.line_segment(
+                [
+                    egui::pos2(x(event.time), rect.top()),
+                    egui::pos2(x(event.time), rect.bottom()),
+                ],
+                egui::Stroke::new(2.0, egui::Color32::from_rgba_unmultiplied(r, g, b, a)),
+            );
+        }
+        if let Some(pointer) = response
+            .clicked()
+            .then(|| response.interact_pointer_pos())
+            .flatten()
+        {
+            let marker = keys
+                .iter()
+                .copied()
+                .chain(events.iter().map(|event| event.time))
+                .map(|time| (time, (x(time) - pointer.x).abs()))
+                .filter(|&(_, distance)| distance <= MARKER_PICK_DISTANCE)
+                .min_by(|a, b| a.1.total_cmp(&b.1));
+            if let Some((marker, _)) = marker {
+                time = marker.min(duration);
+                changed = true;
+            }
+        }
+        let hovered_event = response.hover_pos().and_then(|pointer| {
+            events
+                .iter()
+                .find(|event| (x(event.time) - pointer.x).abs() <= MARKER_PICK_DISTANCE)
+        });
+        if let Some(event) = hovered_event {
+            response.on_hover_text(format!("{:.3}s: {}", event.time, event.kind.describe()));
+        }
+        ui.label(format!(
+            "{:.3}s / {duration:.3}s (elapsed {:.3}s)",
+            player.seek_time(),
+            player.elapsed()
+        ));
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "frame {} / {} at {} fps",
+                frame_step.frame_at(player.seek_time()),
+                frame_step.frame_at(duration),
+                frame_step.fps
+            ));
+            ui.add(egui::DragValue::new(&mut *target_frame).clamp_range(0..=i64::MAX));
+            if ui.button("go").clicked() {
+                time = frame_step.frame_time(*target_frame, duration);
+                changed = true;
+            }
+        });
+        if loop_range.is_set() {
+            let (start, end) = loop_range.bounds(duration);
+            ui.label(format!(
+                "looping {start:.3}s - {end:.3}s (I / U, shift to clear)"
+            ));
+            ui.horizontal(|ui| {
+                ui.label("crossfade the seam over");
+                ui.add(
+                    egui::DragValue::new(&mut crossfade)
+                        .speed(0.01)
+                        .clamp_range(0.0..=2.0)
+                        .suffix("s"),
+                );
+            });
+        }
+    });
+    if crossfade != loop_range.crossfade {
+        loop_range.crossfade = crossfade;
+    }
+    if zoom != view.zoom || pan != view.start {
+        view.zoom = zoom;
+        view.start = pan;
+    }
+
+    if dragging && resume_after_drag.is_none() {
+        *resume_after_drag = Some(!player.is_paused());
+        player.pause();
+    }
+    if changed {
+        player.seek_to(time);
+    }
+    if !dragging {
+        if let Some(resume) = resume_after_drag.take() {
+            if resume {
+                player.resume();
+            }
+        }
+    }
+}
+
+/// I / U set the in / out point to the playhead, shift with either clears
+/// both. Switching clips clears them too.
+fn set_loop_range(
+    keyboard_input: Res<Input<KeyCode>>,
+    current_animation: Res<CurrentAnimation>,
+    mut range: ResMut<LoopRange>,
+    players: Query<&AnimationPlayer>,
+) {
+    if current_animation.is_changed() && range.is_set() {
+        range.clear();
+        println!("loop range cleared");
+    }
+    let Some(player) = players.iter().next() else {
+        return;
+    };
+    if !keyboard_input.any_just_pressed([KeyCode::I, KeyCode::U]) {
+        return;
+    }
+    if keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        range.clear();
+        println!("loop range cleared");
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::I) {
+        range.in_point = Some(player.seek_time());
+    }
+    if keyboard_input.just_pressed(KeyCode::U) {
+        range.out_point = Some(player.seek_time());
+    }
+    println!("loop range: {:?} - {:?}", range.in_point, range.out_point);
+}
+
+/// Moves the playhead back to the in point once it passes the out point, or
+/// to the out point when playing in reverse. With a crossfade it lands that
+/// far past the in point, where `crossfade_loop_seam` has already blended to.
+fn wrap_to_loop_range(
+    range: Res<LoopRange>,
+    clips: Res<Assets<AnimationClip>>,
+    mut players: Query<&mut AnimationPlayer>,
+) {
+    for mut player in &mut players {
+        if player.is_paused() {
+            continue;
+        }
+        let Some(duration) = clips
+            .get(player.animation_clip())
+            .map(|clip| clip.duration())
+        else {
+            continue;
+        };
+        let (start, end) = range.bounds(duration);
+        if end <= start {
+            continue;
+        }
+        let time = player.seek_time();
+        let fade = range.crossfade_within(start, end);
+        if player.speed() >= 0.0 && time >= end {
+            // Carry the overshoot across a crossfaded seam to match the pose
+            // already blended to.
+            let landing = if fade > 0.0 {
+                start + fade + (time - end)
+            } else {
+                start
+            };
+            player.seek_to(landing.min(end));
+        } else if player.speed() >= 0.0 && time < start {
+            player.seek_to(start);
+        } else if player.speed() < 0.0 && (time <= start || time > end) {
+            player.seek_to(end);
+        }
+    }
+}
+
+/// Over the last `crossfade` seconds before the out point, blends the pose
+/// towards the one the same distance past the in point, reaching it at the
+/// out point where `wrap_to_loop_range` jumps there.
+fn crossfade_loop_seam(
+    range: Res<LoopRange>,
+    skeleton: Res<Skeleton>,
+    clips: Res<Assets<AnimationClip>>,
+    players: Query<&AnimationPlayer>,
+    mut transforms: Query<&mut Transform>,
+) {
+    let Some(player) = players.iter().next().filter(|player| player.speed() >= 0.0) else {
+        return;
+    };
+    let Some(clip) = clips.get(player.animation_clip()) else {
+        return;
+    };
+    let (start, end) = range.bounds(clip.duration());
+    let fade = range.crossfade_within(start, end);
+    let into_fade = player.seek_time() - (end - fade);
+    if fade <= 0.0 || into_fade < 0.0 {
+        return;
+    }
+
+    let weight = (into_fade / fade).min(1.0);
+    let pose = sample_pose(clip, &skeleton, start + into_fade);
+    for (bone, target) in skeleton.bones.iter().zip(pose) {
+        let Ok(mut transform) = transforms.get_mut(bone.entity) else {
+            continue;
+        };
+        transform.translation = transform.translation.lerp(target.translation, weight);
+        transform.rotation = transform.rotation.slerp(target.rotation, weight);
+        transform.scale = transform.scale.lerp(target.scale, weight);
+    }
+}
+
+const BOOKMARK_KEYS: [KeyCode; 9] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+];
+
+/// A number key bookmarks the playhead in the current clip; with shift it
+/// jumps back to that bookmark.
+fn bookmark_keys(
+    keyboard_input: Res<Input<KeyCode>>,
+    current_animation: Res<CurrentAnimation>,
+    animation_meta: Res<AnimationsMetadata>,
+    mut bookmarks: ResMut<Bookmarks>,
+    mut players: Query<&mut AnimationPlayer>,
+) {
+    let Some(slot) = BOOKMARK_KEYS
+        .iter()
+        .position(|&key| keyboard_input.just_pressed(key))
+    else {
+        return;
+    };
+    let Some(params) = animation_meta.0.get(current_animation.0) else {
+        return;
+    };
+    let Some(mut player) = players.iter_mut().next() else {
+        return;
+    };
+
+    if keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        match bookmarks.0.get(&params.name).and_then(|slots| slots[slot]) {
+            Some(time) => {
+                player.pause();
+                player.seek_to(time);
+                println!("bookmark {}: {} @ {time:.3}s", slot + 1, params.name);
+            }
+            None => println!("bookmark {}: not set for {}", slot + 1, params.name),
+        }
+    } else {
+        let time = player.seek_time();
+        bookmarks.0.entry(params.name.clone()).or_default()[slot] = Some(time);
+        println!("bookmark {} set: {} @ {time:.3}s", slot + 1, params.name);
+    }
+}
+
+fn toggle_loop_mode(keyboard_input: Res<Input<KeyCode>>, mut mode: ResMut<LoopMode>) {
+    if keyboard_input.just_pressed(KeyCode::L) {
+        *mode = mode.next();
+        println!("loop mode: {:?}", *mode);
+    }
+}
+
+/// Keeps each player's repeat mode in line with the loop mode. Checked every
+/// frame, since starting a clip resets it, even the same clip on a paused
+/// player; runs after everything that switches clips, so whichever tool
+/// started the clip, it repeats the way the loop mode says. A fractional loop
+/// run sets its own count and is left alone.
+fn apply_loop_mode(mode: Res<LoopMode>, mut players: Query<&mut AnimationPlayer>) {
+    for mut player in &mut players {
+        if player.repeat_mode() != mode.repeat() {
+            player.set_repeat(mode.repeat());
+        }
+        if mode.is_changed() && *mode != LoopMode::PingPong && player.speed() < 0.0 {
+            let speed = player.speed();
+            player.set_speed(-speed);
+        }
+    }
+}
+
+/// Pauses a player that has played its clip once exactly on the end pose, as
+/// the finished player is left a little past the end, and restarts it from
+/// the first frame when it's resumed.
+fn hold_end_pose(
+    clips: Res<Assets<AnimationClip>>,
+    mut players: Query<(Entity, &mut AnimationPlayer)>,
+    mut held: Local<HashSet<Entity>>,
+) {
+    for (entity, mut player) in &mut players {
+        if !player.is_finished() {
+            held.remove(&entity);
+            continue;
+        }
+        let Some(duration) = clips
+            .get(player.animation_clip())
+            .map(|clip| clip.duration())
+        else {
+            continue;
+        };
+        let end = if player.speed() >= 0.0 { duration } else { 0.0 };
+        if held.contains(&entity) {
+            if !player.is_paused() {
+                held.remove(&entity);
+                player.replay();
+                if end == 0.0 {
+                    player.seek_to(duration);
+                }
+            }
+            continue;
+        }
+        player.seek_to(end);
+        player.pause();
+        held.insert(entity);
+        println!("reached end.");
+    }
+}
+
+/// Runs before the player advances: if this frame's step would cross an end
+/// of the clip, flips the speed and moves the playhead so that the step lands
+/// on the reflected time, instead of wrapping around and reversing a frame
+/// late.
+fn reverse_at_clip_ends(
+    time: Res<Time>,
+    clips: Res<Assets<AnimationClip>>,
+    mut players: Query<&mut AnimationPlayer>,
+) {
+    for mut player in &mut players {
+        if player.is_paused() {
+            continue;
+        }
+        let Some(duration) = clips
+            .get(player.animation_clip())
+            .map(|clip| clip.duration())
+        else {
+            continue;
+        };
+        let speed = player.speed();
+        let step = speed * time.delta_seconds();
+        let next = player.seek_time() + step;
+        let reflected = if speed > 0.0 && next >= duration {
+            2.0 * duration - next
+        } else if speed < 0.0 && next < 0.0 {
+            -next
+        } else {
+            continue;
+        };
+        player.set_speed(-speed);
+        player.seek_to(reflected + step);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AnimationParams;
+
+    #[test]
+    fn speed_limits_keep_the_sign() {
+        let limits = SpeedLimits::default();
+        assert_eq!(limits.adjust(1.0, 0.5), 1.5);
+        assert_eq!(limits.adjust(-1.0, 0.5), -1.5);
+        assert_eq!(limits.set(-2.0, 0.0), -limits.min);
+    }
+
+    #[test]
+    fn speed_limits_clamp_the_magnitude() {
+        let limits = SpeedLimits { min: 0.1, max: 4.0 };
+        assert_eq!(limits.adjust(0.2, -0.5), 0.1);
+        assert_eq!(limits.adjust(-0.2, -0.5), -0.1);
+        assert_eq!(limits.adjust(3.9, 1.0), 4.0);
+        assert_eq!(limits.set(1.0, 10.0), 4.0);
+    }
+
+    #[test]
+    fn frame_step_rounds_to_the_nearest_frame() {
+        let config = FrameStepConfig { fps: 30.0 };
+        assert_eq!(config.frame_at(0.5), 15);
+        assert_eq!(config.frame_at(0.51), 15);
+        assert_eq!(config.frame_at(0.52), 16);
+    }
+
+    #[test]
+    fn frame_step_moves_strictly_past_the_current_frame() {
+        let config = FrameStepConfig { fps: 30.0 };
+        assert_eq!(config.next_frame(0.5), 16);
+        assert_eq!(config.previous_frame(0.5), 14);
+        assert_eq!(config.next_frame(0.51), 16);
+        assert_eq!(config.previous_frame(0.51), 15);
+    }
+
+    #[test]
+    fn slideshow_skips_clips_and_wraps() {
+        let meta = AnimationsMetadata(
+            ["TPose", "Walk", "Run"]
+                .iter()
+                .map(|name| AnimationParams::new("clips.glb", name))
+                .collect(),
+        );
+        let config = SlideshowConfig::default();
+        assert_eq!(config.next_clip(&meta, 1), Some(2));
+        assert_eq!(config.next_clip(&meta, 2), Some(1));
+    }
+
+    #[test]
+    fn slideshow_without_clips_has_no_next() {
+        let config = SlideshowConfig::default();
+        assert_eq!(config.next_clip(&AnimationsMetadata(Vec::new()), 0), None);
+        let only_skipped = AnimationsMetadata(vec![AnimationParams::new("clips.glb", "TPose")]);
+        assert_eq!(config.next_clip(&only_skipped, 0), None);
+    }
+
+    #[test]
+    fn frame_time_is_clamped_to_the_clip() {
+        let config = FrameStepConfig { fps: 30.0 };
+        assert_eq!(config.frame_time(15, 1.0), 0.5);
+        assert_eq!(config.frame_time(-1, 1.0), 0.0);
+        assert_eq!(config.frame_time(40, 1.0), 1.0);
+    }
+}
@@ -0,0 +1,445 @@
+//! Named static poses that override the playing clip.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bevy::animation::animation_player;
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+use bevy::utils::HashMap;
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+use bevy_inspector_egui::egui;
+use serde::{Deserialize, Serialize};
+
+use crate::skeleton::Skeleton;
+use crate::ui::keyboard_free;
+use crate::{Animations, AnimationsMetadata};
+
+pub const POSE_LIBRARY_PATH: &str = "assets/poses.ron";
+
+/// Local bone transforms keyed by bone name.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Pose {
+    pub name: String,
+    pub bones: BTreeMap<String, Transform>,
+}
+
+impl Pose {
+    /// The bones' current local transforms, keyed by their glTF node names.
+    pub fn capture(name: String, skeleton: &Skeleton, transforms: &Query<&Transform>) -> Self {
+        Self {
+            name,
+            bones: skeleton
+                .bones
+                .iter()
+                .filter_map(|bone| Some((bone.name.clone(), *transforms.get(bone.entity).ok()?)))
+                .collect(),
+        }
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(path, text)
+    }
+}
+
+/// Names the pose on screen by clip and time, e.g. `"Walk @ 0.533s"`.
+fn playing_label(
+    animations: Option<&Animations>,
+    animation_meta: &AnimationsMetadata,
+    players: &Query<&AnimationPlayer>,
+) -> String {
+    let Some(player) = players.iter().next() else {
+        return String::new();
+    };
+    let clip = player.animation_clip().id();
+    let name = animations
+        .and_then(|animations| animations.0.iter().position(|handle| handle.id() == clip))
+        .and_then(|index| animation_meta.0.get(index))
+        .map_or("?", |params| params.name.as_str());
+    format!("{name} @ {:.3}s", player.seek_time())
+}
+
+#[derive(Resource, Serialize, Deserialize, Debug, Default)]
+pub struct PoseLibrary {
+    pub poses: Vec<Pose>,
+}
+
+impl PoseLibrary {
+    pub fn from_file(path: &str) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        ron::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// Name of the pose currently overriding playback, if any.
+#[derive(Resource, Debug, Default)]
+pub struct ActivePose(pub Option<String>);
+
+/// When enabled, switching clips snapshots whatever pose is on screen and
+/// fades from it into the new clip, instead of the player's own crossfade
+/// between the two clips.
+#[derive(Resource, Debug)]
+pub struct LivePoseBlend {
+    pub enabled: bool,
+    pub duration: Duration,
+}
+
+impl Default for LivePoseBlend {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            duration: Duration::from_millis(300),
+        }
+    }
+}
+
+/// Local bone transforms captured at a clip switch, and how far the fade out
+/// of them has progressed.
+#[derive(Resource)]
+struct PoseSnapshotBlend {
+    bones: HashMap<Entity, Transform>,
+    elapsed: Duration,
+}
+
+/// Two poses captured from playback, to compare them bone by bone.
+#[derive(Resource, Debug, Default)]
+pub struct PoseComparison {
+    pub a: Option<Pose>,
+    pub b: Option<Pose>,
+}
+
+/// How far one bone moved between two poses.
+#[derive(Debug, Clone)]
+pub struct BoneDifference {
+    pub bone: String,
+    pub degrees: f32,
+    pub distance: f32,
+}
+
+/// Per-bone differences between two poses, largest rotation first.
+pub fn pose_difference(a: &Pose, b: &Pose) -> Vec<BoneDifference> {
+    let mut differences: Vec<BoneDifference> = a
+        .bones
+        .iter()
+        .filter_map(|(bone, from)| {
+            let to = b.bones.get(bone)?;
+            Some(BoneDifference {
+                bone: bone.clone(),
+                degrees: from.rotation.angle_between(to.rotation).to_degrees(),
+                distance: from.translation.distance(to.translation),
+            })
+        })
+        .collect();
+    differences.sort_by(|x, y| {
+        y.degrees
+            .total_cmp(&x.degrees)
+            .then(y.distance.total_cmp(&x.distance))
+    });
+    differences
+}
+
+pub struct PoseLibraryPlugin;
+
+impl Plugin for PoseLibraryPlugin {
+    fn build(&self, app: &mut App) {
+        let library = match PoseLibrary::from_file(POSE_LIBRARY_PATH) {
+            Ok(library) => library,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => PoseLibrary::default(),
+            Err(e) => {
+                println!("pose library: could not load {POSE_LIBRARY_PATH}: {e}");
+                PoseLibrary::default()
+            }
+        };
+        app.insert_resource(library)
+            .init_resource::<ActivePose>()
+            .init_resource::<LivePoseBlend>()
+            .init_resource::<PoseComparison>()
+            .add_systems(
+                Update,
+                (
+                    pose_library_panel,
+                    toggle_live_pose_blend.run_if(keyboard_free),
+                    (
+                        capture_compared_pose.run_if(keyboard_free),
+                        pose_comparison_panel,
+                    )
+                        .chain(),
+                    save_pose_snapshot.run_if(keyboard_free),
+                ),
+            )
+            .add_systems(
+                PostUpdate,
+                (
+                    snapshot_pose_on_clip_change
+                        .run_if(|blend: Res<LivePoseBlend>| blend.enabled)
+                        .before(animation_player),
+                    (
+                        blend_from_snapshot.run_if(resource_exists::<PoseSnapshotBlend>()),
+                        apply_active_pose.run_if(|active: Res<ActivePose>| active.0.is_some()),
+                    )
+                        .chain()
+                        .after(animation_player)
+                        .before(TransformSystem::TransformPropagate),
+                ),
+            );
+    }
+}
+
+fn pose_library_panel(
+    mut contexts: EguiContexts,
+    mut library: ResMut<PoseLibrary>,
+    mut active: ResMut<ActivePose>,
+) {
+    egui::Window::new("Poses")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            if ui
+                .selectable_label(active.0.is_none(), "(play clip)")
+                .clicked()
+            {
+                active.0 = None;
+            }
+            for pose in &library.poses {
+                let selected = active.0.as_deref() == Some(pose.name.as_str());
+                if ui.selectable_label(selected, pose.name.as_str()).clicked() {
+                    active.0 = Some(pose.name.clone());
+                }
+            }
+            ui.separator();
+            if ui.button("reload").clicked() {
+                match PoseLibrary::from_file(POSE_LIBRARY_PATH) {
+                    Ok(reloaded) => *library = reloaded,
+                    Err(e) => println!("pose library: could not load {POSE_LIBRARY_PATH}: {e}"),
+                }
+            }
+        });
+}
+
+fn apply_active_pose(
+    active: Res<ActivePose>,
+    library: Res<PoseLibrary>,
+    skeleton: Res<Skeleton>,
+    mut transforms: Query<&mut Transform>,
+) {
+    let Some(pose) = library
+        .poses
+        .iter()
+        .find(|pose| Some(pose.name.as_str()) == active.0.as_deref())
+    else {
+        return;
+    };
+    for (name, pose_transform) in &pose.bones {
+        let Some(bone) = skeleton.find(name) else {
+            continue;
+        };
+        if let Ok(mut transform) = transforms.get_mut(bone.entity) {
+            *transform = *pose_transform;
+        }
+    }
+}
+
+fn toggle_live_pose_blend(keyboard_input: Res<Input<KeyCode>>, mut blend: ResMut<LivePoseBlend>) {
+    if keyboard_input.just_pressed(KeyCode::F5) {
+        blend.enabled = !blend.enabled;
+        println!("blend from live pose: {}", blend.enabled);
+    }
+}
+
+/// Runs before the animation player, so bone transforms still hold the pose
+/// shown last frame when the clip has just been switched.
+fn snapshot_pose_on_clip_change(
+    mut commands: Commands,
+    skeleton: Res<Skeleton>,
+    players: Query<&AnimationPlayer>,
+    transforms: Query<&Transform>,
+    mut last_clip: Local<Option<AssetId<AnimationClip>>>,
+) {
+    let Some(player) = players.iter().next() else {
+        return;
+    };
+    let clip = player.animation_clip().id();
+    let previous = last_clip.replace(clip);
+    if previous.is_none() || previous == Some(clip) {
+        return;
+    }
+
+    let bones = skeleton
+        .bones
+        .iter()
+        .filter_map(|bone| Some((bone.entity, *transforms.get(bone.entity).ok()?)))
+        .collect();
+    commands.insert_resource(PoseSnapshotBlend {
+        bones,
+        elapsed: Duration::ZERO,
+    });
+}
+
+fn blend_from_snapshot(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<LivePoseBlend>,
+    mut blend: ResMut<PoseSnapshotBlend>,
+    mut transforms: Query<&mut Transform>,
+) {
+    blend.elapsed += time.delta();
+    let t =
+        (blend.elapsed.as_secs_f32() / settings.duration.as_secs_f32().max(f32::EPSILON)).min(1.0);
+    for (&entity, snapshot) in &blend.bones {
+        let Ok(mut transform) = transforms.get_mut(entity) else {
+            continue;
+        };
+        transform.translation = snapshot.translation.lerp(transform.translation, t);
+        transform.rotation = snapshot.rotation.slerp(transform.rotation, t);
+        transform.scale = snapshot.scale.lerp(transform.scale, t);
+    }
+    if t >= 1.0 {
+        commands.remove_resource::<PoseSnapshotBlend>();
+    }
+}
+
+/// `F3` captures pose A and `F4` pose B from the bones as currently shown.
+fn capture_compared_pose(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut comparison: ResMut<PoseComparison>,
+    skeleton: Res<Skeleton>,
+    animations: Option<Res<Animations>>,
+    animation_meta: Res<AnimationsMetadata>,
+    players: Query<&AnimationPlayer>,
+    transforms: Query<&Transform>,
+) {
+    let slot = if keyboard_input.just_pressed(KeyCode::F3) {
+        "A"
+    } else if keyboard_input.just_pressed(KeyCode::F4) {
+        "B"
+    } else {
+        return;
+    };
+
+    let label = playing_label(animations.as_deref(), &animation_meta, &players);
+    let pose = Pose::capture(label, &skeleton, &transforms);
+    println!("captured pose {slot}: {}", pose.name);
+    if slot == "A" {
+        comparison.a = Some(pose);
+    } else {
+        comparison.b = Some(pose);
+    }
+}
+
+fn pose_comparison_panel(mut contexts: EguiContexts, comparison: Res<PoseComparison>) {
+    egui::Window::new("Pose comparison")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            let name = |pose: &Option<Pose>| pose.as_ref().map_or("-", |pose| pose.name.as_str());
+            ui.label(format!("A (F3): {}", name(&comparison.a)));
+            ui.label(format!("B (F4): {}", name(&comparison.b)));
+            let (Some(a), Some(b)) = (&comparison.a, &comparison.b) else {
+                return;
+            };
+
+            let differences = pose_difference(a, b);
+            ui.separator();
+            egui::ScrollArea::vertical()
+                .max_height(300.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("pose_comparison")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.label("bone");
+                            ui.label("rotation (deg)");
+                            ui.label("translation");
+                            ui.end_row();
+                            for difference in &differences {
+                                ui.label(difference.bone.as_str());
+                                ui.label(format!("{:.2}", difference.degrees));
+                                ui.label(format!("{:.4}", difference.distance));
+                                ui.end_row();
+                            }
+                        });
+                });
+        });
+}
+
+/// `P` writes the pose on screen to a timestamped `.ron` file, in the format
+/// of a `PoseLibrary` entry.
+fn save_pose_snapshot(
+    keyboard_input: Res<Input<KeyCode>>,
+    skeleton: Res<Skeleton>,
+    animations: Option<Res<Animations>>,
+    animation_meta: Res<AnimationsMetadata>,
+    players: Query<&AnimationPlayer>,
+    transforms: Query<&Transform>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::P) {
+        return;
+    }
+    let label = playing_label(animations.as_deref(), &animation_meta, &players);
+    let pose = Pose::capture(label, &skeleton, &transforms);
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let path = format!("pose_{timestamp}.ron");
+    match pose.save(&path) {
+        Ok(()) => println!(
+            "saved pose {} ({} bones) to {path}",
+            pose.name,
+            pose.bones.len()
+        ),
+        Err(e) => println!("could not write {path}: {e}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    fn pose(bones: &[(&str, Transform)]) -> Pose {
+        Pose {
+            name: String::new(),
+            bones: bones
+                .iter()
+                .map(|(name, transform)| (name.to_string(), *transform))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn pose_difference_sorts_by_rotation() {
+        let a = pose(&[
+            ("Arm", Transform::IDENTITY),
+            ("Hips", Transform::IDENTITY),
+            ("Spine", Transform::IDENTITY),
+        ]);
+        let b = pose(&[
+            (
+                "Arm",
+                Transform::from_rotation(Quat::from_rotation_z(PI / 2.0)),
+            ),
+            ("Hips", Transform::from_xyz(0.0, 1.0, 0.0)),
+            (
+                "Spine",
+                Transform::from_rotation(Quat::from_rotation_x(PI / 4.0)),
+            ),
+        ]);
+        let differences = pose_difference(&a, &b);
+        let bones: Vec<_> = differences.iter().map(|diff| diff.bone.as_str()).collect();
+        assert_eq!(bones, ["Arm", "Spine", "Hips"]);
+        assert!((differences[0].degrees - 90.0).abs() < 1e-3);
+        assert!((differences[1].degrees - 45.0).abs() < 1e-3);
+        assert!((differences[2].distance - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn pose_difference_skips_bones_missing_from_either_pose() {
+        let a = pose(&[("Arm", Transform::IDENTITY), ("Tail", Transform::IDENTITY)]);
+        let b = pose(&[("Arm", Transform::IDENTITY), ("Wing", Transform::IDENTITY)]);
+        let differences = pose_difference(&a, &b);
+        assert_eq!(differences.len(), 1);
+        assert_eq!(differences[0].bone, "Arm");
+        assert_eq!(differences[0].degrees, 0.0);
+    }
+}
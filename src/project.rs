@@ -0,0 +1,77 @@
+//! Per-project settings bundled into one `.project.ron` file, loaded with
+//! `--project <file>`.
+//!
+//! Every section is optional; anything left out keeps the built-in default.
+//! `keybindings` lists only the actions it remaps, like
+//! `assets/keybindings.ron`, and replaces that file's bindings.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::camera::{CameraPreset, CameraPresets};
+use crate::keybindings::{Action, KeyBindings};
+use crate::ui::{clamp_ui_scale, UiDock, UiSettings};
+use crate::{AnimationParams, AnimationsFile, AnimationsMetadata, GizmosConfig, ModelConfig};
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct ProjectFile {
+    /// Scene asset spawned as the character, e.g. `"hero.glb#Scene0"`.
+    pub model: Option<String>,
+    pub animations: Option<Vec<AnimationParams>>,
+    pub camera_presets: Option<Vec<CameraPreset>>,
+    pub gizmos: Option<GizmosConfig>,
+    pub keybindings: Option<HashMap<Action, KeyCode>>,
+    pub ui_dock: Option<UiDock>,
+    /// Overrides the scale kept in `assets/ui_settings.ron` for this project.
+    pub ui_scale: Option<f32>,
+}
+
+impl ProjectFile {
+    pub fn from_file(path: &str) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        ron::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Overrides the app's resources with the sections present in the file.
+    pub fn apply(self, app: &mut App) {
+        if let Some(model) = self.model {
+            app.world.resource_mut::<ModelConfig>().scene = model;
+        }
+        match self.animations {
+            Some(animations) if animations.is_empty() => {
+                println!("warning: the project lists no animations; keeping the animation list");
+            }
+            Some(animations) => {
+                app.insert_resource(AnimationsMetadata(animations));
+                app.insert_resource(AnimationsFile(None));
+            }
+            None => {}
+        }
+        if let Some(presets) = self.camera_presets {
+            app.insert_resource(CameraPresets {
+                presets,
+                current: None,
+                file: None,
+            });
+        }
+        if let Some(gizmos) = self.gizmos {
+            app.insert_resource(gizmos);
+        }
+        if let Some(keybindings) = self.keybindings {
+            app.insert_resource(KeyBindings::with_overrides(keybindings));
+        }
+        if let Some(dock) = self.ui_dock {
+            app.world.resource_mut::<UiSettings>().dock = dock;
+        }
+        if let Some(scale) = self.ui_scale {
+            let mut settings = app.world.resource_mut::<UiSettings>();
+            settings.scale = clamp_ui_scale(scale);
+            settings.file = None;
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//! Respawns the character when its glTF changes on disk, so a fresh export
+//! from the DCC tool shows up without restarting. Clips reload in place into
+//! the same handles, so the player keeps its clip, time and speed.
+
+use bevy::prelude::*;
+
+use crate::{setup_scene_once_loaded, Animations, AnimationsMetadata, ModelRoot};
+
+/// Playback state of the character being replaced, restored onto the new
+/// one's player.
+#[derive(Resource)]
+struct ReloadedPlayback {
+    clip: Handle<AnimationClip>,
+    seek_time: f32,
+    speed: f32,
+    paused: bool,
+}
+
+pub struct ModelReloadPlugin;
+
+impl Plugin for ModelReloadPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                respawn_changed_model,
+                log_reloaded_clips.run_if(resource_exists::<Animations>()),
+                restore_playback
+                    .after(setup_scene_once_loaded)
+                    .run_if(resource_exists::<ReloadedPlayback>()),
+            ),
+        );
+    }
+}
+
+fn respawn_changed_model(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<Scene>>,
+    roots: Query<(Entity, &Transform, &ModelRoot, &Handle<Scene>)>,
+    players: Query<&AnimationPlayer>,
+) {
+    for event in events.read() {
+        let AssetEvent::Modified { id } = event else {
+            continue;
+        };
+        for (entity, transform, root, scene) in &roots {
+            if scene.id() != *id {
+                continue;
+            }
+            if let Some(player) = players.iter().next() {
+                commands.insert_resource(ReloadedPlayback {
+                    clip: player.animation_clip().clone_weak(),
+                    seek_time: player.seek_time(),
+                    speed: player.speed(),
+                    paused: player.is_paused(),
+                });
+            }
+            commands.entity(entity).despawn_recursive();
+            commands.spawn((
+                SceneBundle {
+                    scene: scene.clone(),
+                    transform: *transform,
+                    ..default()
+                },
+                ModelRoot { base: root.base },
+            ));
+            println!("model changed on disk, reloading");
+        }
+    }
+}
+
+fn restore_playback(
+    mut commands: Commands,
+    reloaded: Res<ReloadedPlayback>,
+    mut players: Query<&mut AnimationPlayer, Added<AnimationPlayer>>,
+) {
+    for mut player in &mut players {
+        player.play(reloaded.clip.clone_weak());
+        player.seek_to(reloaded.seek_time);
+        player.set_speed(reloaded.speed);
+        if reloaded.paused {
+            player.pause();
+        }
+        commands.remove_resource::<ReloadedPlayback>();
+    }
+}
+
+fn log_reloaded_clips(
+    mut events: EventReader<AssetEvent<AnimationClip>>,
+    animations: Res<Animations>,
+    animation_meta: Res<AnimationsMetadata>,
+) {
+    for event in events.read() {
+        let AssetEvent::Modified { id } = event else {
+            continue;
+        };
+        if let Some(index) = animations.0.iter().position(|handle| handle.id() == *id) {
+            println!(
+                "animation changed on disk: {}",
+                animation_meta.0[index].name
+            );
+        }
+    }
+}
@@ -0,0 +1,256 @@
+//! Previews of a clip on a skeleton that differs from the one it was authored for.
+
+use std::fs;
+use std::io;
+
+use bevy::animation::{animation_player, EntityPath};
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+use bevy::utils::{HashMap, HashSet};
+use serde::Deserialize;
+
+use crate::diagnostics::{clip_paths, path_bone_name};
+use crate::skeleton::Skeleton;
+use crate::ui::keyboard_free;
+use crate::{Animations, AnimationsMetadata};
+
+pub const BONE_MAP_PATH: &str = "assets/bone_map.ron";
+
+/// Source to target node names, e.g. `{ "mixamorig:Hips": "pelvis" }`, to
+/// play clips authored for one rig's naming on a rig named differently. Only
+/// renames: the two hierarchies must have the same shape. Nodes not listed
+/// keep their name.
+#[derive(Resource, Deserialize, Debug, Default)]
+pub struct BoneMap(pub HashMap<String, String>);
+
+impl BoneMap {
+    pub fn from_file(path: &str) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        ron::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn rename(&self, path: &EntityPath) -> EntityPath {
+        EntityPath {
+            parts: path
+                .parts
+                .iter()
+                .map(|name| match self.0.get(name.as_str()) {
+                    Some(target) => Name::new(target.clone()),
+                    None => name.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// A copy of `clip` with its curve targets renamed. Tracks that still
+    /// don't reach a node in `node_paths` are left out; their source bone
+    /// names are returned alongside.
+    pub fn retarget(
+        &self,
+        clip: &AnimationClip,
+        node_paths: &HashSet<EntityPath>,
+    ) -> (AnimationClip, Vec<String>) {
+        let mut retargeted = AnimationClip::default();
+        let mut skipped = Vec::new();
+        for (path, index) in clip_paths(clip) {
+            let target = self.rename(path);
+            if !node_paths.contains(&target) {
+                skipped.push(path_bone_name(path).to_string());
+                continue;
+            }
+            for curve in clip.get_curves(index).into_iter().flatten() {
+                retargeted.add_curve_to_path(target.clone(), curve.clone());
+            }
+        }
+        skipped.sort();
+        (retargeted, skipped)
+    }
+}
+
+/// Loaded clips paired with the renamed copies that replace them in
+/// `Animations`. The originals are kept so a change on disk can be renamed
+/// again.
+#[derive(Resource, Default)]
+struct RetargetedClips(Vec<(Handle<AnimationClip>, Handle<AnimationClip>)>);
+
+/// Bone-length ratios applied on top of the playing clip. A ratio on a bone
+/// scales the offsets of its children, i.e. the length of the segment that
+/// starts at that bone.
+#[derive(Resource, Debug)]
+pub struct ProportionsConfig {
+    pub enabled: bool,
+    pub ratios: HashMap<String, f32>,
+}
+
+impl Default for ProportionsConfig {
+    fn default() -> Self {
+        let ratios = ["LeftUpLeg", "LeftLeg", "RightUpLeg", "RightLeg"]
+            .into_iter()
+            .map(|bone| (bone.to_string(), 1.2))
+            .collect();
+        Self {
+            enabled: false,
+            ratios,
+        }
+    }
+}
+
+/// Translation each scaled bone was given last frame, and the unscaled value it
+/// was derived from. Bones whose translation isn't animated keep our write, so
+/// this is what stops the scale compounding frame over frame.
+#[derive(Resource, Default)]
+struct ScaledTranslations(HashMap<Entity, (Vec3, Vec3)>);
+
+pub struct RetargetPlugin;
+
+impl Plugin for RetargetPlugin {
+    fn build(&self, app: &mut App) {
+        let bone_map = match BoneMap::from_file(BONE_MAP_PATH) {
+            Ok(bone_map) => bone_map,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => BoneMap::default(),
+            Err(e) => {
+                println!("retarget: could not load {BONE_MAP_PATH}: {e}");
+                BoneMap::default()
+            }
+        };
+        app.init_resource::<ProportionsConfig>()
+            .init_resource::<ScaledTranslations>()
+            .init_resource::<RetargetedClips>()
+            .insert_resource(bone_map)
+            .add_systems(
+                Update,
+                (
+                    toggle_proportions.run_if(keyboard_free),
+                    retarget_clips
+                        .run_if(|bone_map: Res<BoneMap>| !bone_map.0.is_empty())
+                        .run_if(resource_exists::<Animations>()),
+                ),
+            )
+            .add_systems(
+                PostUpdate,
+                apply_proportions
+                    .run_if(|config: Res<ProportionsConfig>| config.enabled)
+                    .after(animation_player)
+                    .before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+fn toggle_proportions(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut config: ResMut<ProportionsConfig>,
+    mut scaled: ResMut<ScaledTranslations>,
+    mut transforms: Query<&mut Transform>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::Semicolon) {
+        return;
+    }
+    config.enabled = !config.enabled;
+    if !config.enabled {
+        for (entity, (written, base)) in scaled.0.drain() {
+            if let Ok(mut transform) = transforms.get_mut(entity) {
+                if transform.translation == written {
+                    transform.translation = base;
+                }
+            }
+        }
+    }
+    println!("proportion preview: {}", config.enabled);
+}
+
+fn apply_proportions(
+    config: Res<ProportionsConfig>,
+    skeleton: Res<Skeleton>,
+    mut scaled: ResMut<ScaledTranslations>,
+    mut transforms: Query<&mut Transform>,
+) {
+    for (name, &ratio) in &config.ratios {
+        let Some(index) = skeleton.index_of(name) else {
+            continue;
+        };
+        for child in skeleton.children_of(index) {
+            let entity = skeleton.bones[child].entity;
+            let Ok(mut transform) = transforms.get_mut(entity) else {
+                continue;
+            };
+            let base = match scaled.0.get(&entity) {
+                Some(&(written, base)) if transform.translation == written => base,
+                _ => transform.translation,
+            };
+            transform.translation = base * ratio;
+            scaled.0.insert(entity, (transform.translation, base));
+        }
+    }
+}
+
+/// Swaps each clip in `Animations` for a copy renamed through the bone map
+/// once both it and the skeleton have loaded, moving the player over if it's
+/// playing the original.
+fn retarget_clips(
+    bone_map: Res<BoneMap>,
+    skeleton: Res<Skeleton>,
+    animation_meta: Res<AnimationsMetadata>,
+    mut animations: ResMut<Animations>,
+    mut clips: ResMut<Assets<AnimationClip>>,
+    mut retargeted: ResMut<RetargetedClips>,
+    mut events: EventReader<AssetEvent<AnimationClip>>,
+    mut players: Query<&mut AnimationPlayer>,
+) {
+    if skeleton.bones.is_empty() {
+        events.clear();
+        return;
+    }
+    let changed: Vec<AssetId<AnimationClip>> = events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+
+    for index in 0..animations.0.len() {
+        let handle = animations.0[index].clone();
+        let name = animation_meta
+            .0
+            .get(index)
+            .map_or("?", |params| params.name.as_str());
+        let existing = retargeted
+            .0
+            .iter()
+            .find(|(_, copy)| copy.id() == handle.id())
+            .cloned();
+        let source = match &existing {
+            Some((source, _)) if changed.contains(&source.id()) => source.clone(),
+            Some(_) => continue,
+            None => handle,
+        };
+        let Some(clip) = clips.get(&source) else {
+            continue;
+        };
+
+        let (clip, skipped) = bone_map.retarget(clip, &skeleton.node_paths);
+        for bone in &skipped {
+            println!("[{name}] retarget: no mapping for {bone}, skipping its track");
+        }
+        if let Some((_, copy)) = existing {
+            clips.insert(copy.id(), clip);
+            continue;
+        }
+        let copy = clips.add(clip);
+        for mut player in &mut players {
+            if player.animation_clip().id() != source.id() {
+                continue;
+            }
+            let (seek_time, speed, paused) =
+                (player.seek_time(), player.speed(), player.is_paused());
+            player.play(copy.clone_weak());
+            player.seek_to(seek_time);
+            player.set_speed(speed);
+            if paused {
+                player.pause();
+            }
+        }
+        animations.0[index] = copy.clone();
+        retargeted.0.push((source, copy));
+    }
+}
@@ -0,0 +1,275 @@
+//! Samples clips on the CPU, independently of the `AnimationPlayer`, for tools
+//! that need poses at times other than the one being played.
+
+use bevy::animation::{Interpolation, Keyframes, VariableCurve};
+use bevy::prelude::*;
+
+use crate::diagnostics::clip_paths;
+use crate::skeleton::Skeleton;
+
+/// Index of the keyframe at or before `time` and the fraction of the way to
+/// the next one, clamped to the curve's ends.
+fn keyframe_step(timestamps: &[f32], time: f32) -> (usize, usize, f32) {
+    let last = timestamps.len().saturating_sub(1);
+    let next = timestamps.partition_point(|&t| t <= time).min(last);
+    if next == 0 {
+        return (0, 0, 0.0);
+    }
+    let step = next - 1;
+    if time >= timestamps[last] {
+        return (last, last, 0.0);
+    }
+    let span = timestamps[next] - timestamps[step];
+    let lerp = if span > 0.0 {
+        (time - timestamps[step]) / span
+    } else {
+        0.0
+    };
+    (step, next, lerp)
+}
+
+/// Hermite interpolation between two cubic spline keyframes, as glTF defines
+/// it.
+fn cubic_spline<T>(v0: T, out0: T, in1: T, v1: T, t: f32, span: f32) -> T
+where
+    T: std::ops::Mul<f32, Output = T> + std::ops::Add<Output = T>,
+{
+    let t2 = t * t;
+    let t3 = t2 * t;
+    v0 * (2.0 * t3 - 3.0 * t2 + 1.0)
+        + out0 * (span * (t3 - 2.0 * t2 + t))
+        + v1 * (-2.0 * t3 + 3.0 * t2)
+        + in1 * (span * (t3 - t2))
+}
+
+fn sample_vec3(
+    values: &[Vec3],
+    interpolation: &Interpolation,
+    step: usize,
+    next: usize,
+    t: f32,
+    span: f32,
+) -> Vec3 {
+    match interpolation {
+        Interpolation::Step => values[step],
+        Interpolation::Linear => values[step].lerp(values[next], t),
+        Interpolation::CubicSpline => cubic_spline(
+            values[step * 3 + 1],
+            values[step * 3 + 2],
+            values[next * 3],
+            values[next * 3 + 1],
+            t,
+            span,
+        ),
+    }
+}
+
+/// Applies `curve` at `time` to the matching channel of `transform`.
+pub fn sample_curve(curve: &VariableCurve, time: f32, transform: &mut Transform) {
+    let timestamps = &curve.keyframe_timestamps;
+    if timestamps.is_empty() {
+        return;
+    }
+    let (step, next, t) = keyframe_step(timestamps, time);
+    let span = timestamps[next] - timestamps[step];
+    let interpolation = &curve.interpolation;
+    match &curve.keyframes {
+        Keyframes::Rotation(values) => {
+            transform.rotation = match interpolation {
+                Interpolation::Step => values[step],
+                Interpolation::Linear => values[step].slerp(values[next], t),
+                Interpolation::CubicSpline => {
+                    let [v0, out0, in1, v1] = [
+                        values[step * 3 + 1],
+                        values[step * 3 + 2],
+                        values[next * 3],
+                        values[next * 3 + 1],
+                    ]
+                    .map(Vec4::from);
+                    Quat::from_vec4(cubic_spline(v0, out0, in1, v1, t, span))
+                }
+            }
+            .normalize();
+        }
+        Keyframes::Translation(values) => {
+            transform.translation = sample_vec3(values, interpolation, step, next, t, span);
+        }
+        Keyframes::Scale(values) => {
+            transform.scale = sample_vec3(values, interpolation, step, next, t, span);
+        }
+        Keyframes::Weights(_) => {}
+    }
+}
+
+/// Every time at which any of the clip's curves has a keyframe, in order.
+pub fn key_times(clip: &AnimationClip) -> Vec<f32> {
+    let mut times: Vec<f32> = clip
+        .curves()
+        .iter()
+        .flatten()
+        .flat_map(|curve| curve.keyframe_timestamps.iter().copied())
+        .collect();
+    times.sort_by(f32::total_cmp);
+    times.dedup();
+    times
+}
+
+/// Local transform of every skeleton bone in `clip` at `time`. Channels the
+/// clip doesn't animate keep the bone's rest value.
+pub fn sample_pose(clip: &AnimationClip, skeleton: &Skeleton, time: f32) -> Vec<Transform> {
+    let mut pose: Vec<Transform> = skeleton.bones.iter().map(|bone| bone.rest).collect();
+    for (path, index) in clip_paths(clip) {
+        let Some(bone) = skeleton.bones.iter().position(|bone| &bone.path == path) else {
+            continue;
+        };
+        for curve in clip.get_curves(index).into_iter().flatten() {
+            sample_curve(curve, time, &mut pose[bone]);
+        }
+    }
+    pose
+}
+
+/// World transforms for a pose of local transforms. The root bones hang from
+/// their parent's current global transform; nodes between two bones are
+/// skipped, which holds for rigs whose skin joints form one hierarchy.
+pub fn pose_world(
+    skeleton: &Skeleton,
+    pose: &[Transform],
+    parents: &Query<&Parent>,
+    global_transforms: &Query<&GlobalTransform>,
+) -> Vec<Transform> {
+    let mut world: Vec<Transform> = Vec::with_capacity(pose.len());
+    for (bone, local) in skeleton.bones.iter().zip(pose) {
+        let parent_world = match bone.parent {
+            Some(parent) => world[parent],
+            None => parents
+                .get(bone.entity)
+                .ok()
+                .and_then(|parent| global_transforms.get(parent.get()).ok())
+                .map(|global| global.compute_transform())
+                .unwrap_or_default(),
+        };
+        world.push(parent_world.mul_transform(*local));
+    }
+    world
+}
+
+/// A pose mirrored across the skeleton's YZ plane: every bone takes its
+/// other-side counterpart's rotation away from the rest pose, reflected, and
+/// the root bones' translations are reflected too. Working from the rest pose
+/// keeps bones whose local axes aren't mirror images of each other correct.
+pub fn mirror_pose(skeleton: &Skeleton, pose: &[Transform]) -> Vec<Transform> {
+    let posed = skeleton_rotations(skeleton, pose.iter().map(|local| local.rotation));
+    let rest = skeleton_rotations(
+        skeleton,
+        skeleton.bones.iter().map(|bone| bone.rest.rotation),
+    );
+    // Conjugating by a reflection of x keeps the x component and negates the
+    // other two axes.
+    let reflect = |q: Quat| Quat::from_xyzw(q.x, -q.y, -q.z, q.w);
+
+    let mirrored: Vec<Quat> = (0..skeleton.bones.len())
+        .map(|i| {
+            let j = skeleton.mirror_of(i);
+            reflect(posed[j] * rest[j].inverse()) * rest[i]
+        })
+        .collect();
+    skeleton
+        .bones
+        .iter()
+        .enumerate()
+        .map(|(i, bone)| {
+            let mut local = pose[i];
+            match bone.parent {
+                Some(parent) => local.rotation = mirrored[parent].inverse() * mirrored[i],
+                None => {
+                    let t = pose[skeleton.mirror_of(i)].translation;
+                    local.translation = Vec3::new(-t.x, t.y, t.z);
+                    local.rotation = mirrored[i];
+                }
+            }
+            local
+        })
+        .collect()
+}
+
+/// Rotations of every bone relative to the root bones' parent.
+fn skeleton_rotations(skeleton: &Skeleton, locals: impl Iterator<Item = Quat>) -> Vec<Quat> {
+    let mut rotations: Vec<Quat> = Vec::with_capacity(skeleton.bones.len());
+    for (bone, local) in skeleton.bones.iter().zip(locals) {
+        let parent = bone
+            .parent
+            .map_or(Quat::IDENTITY, |parent| rotations[parent]);
+        rotations.push(parent * local);
+    }
+    rotations
+}
+
+/// Draws a pose as lines from each bone to its parent.
+pub fn draw_pose(gizmos: &mut Gizmos, skeleton: &Skeleton, world: &[Transform], color: Color) {
+    for (bone, transform) in skeleton.bones.iter().zip(world) {
+        if let Some(parent) = bone.parent {
+            gizmos.line(world[parent].translation, transform.translation, color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::skeleton::Bone;
+
+    fn bone(name: &str, parent: Option<usize>) -> Bone {
+        Bone {
+            entity: Entity::PLACEHOLDER,
+            name: name.to_string(),
+            parent,
+            path: default(),
+            rest: Transform::IDENTITY,
+        }
+    }
+
+    fn skeleton() -> Skeleton {
+        Skeleton {
+            bones: vec![
+                bone("Hips", None),
+                bone("LeftArm", Some(0)),
+                bone("RightArm", Some(0)),
+            ],
+            ..default()
+        }
+    }
+
+    #[test]
+    fn mirror_pose_swaps_sides() {
+        let skeleton = skeleton();
+        let pose = [
+            Transform::from_xyz(1.0, 2.0, 3.0),
+            Transform::from_rotation(Quat::from_rotation_y(0.5)),
+            Transform::IDENTITY,
+        ];
+        let mirrored = mirror_pose(&skeleton, &pose);
+        assert!(mirrored[0]
+            .translation
+            .abs_diff_eq(Vec3::new(-1.0, 2.0, 3.0), 1e-5));
+        assert!(mirrored[1].rotation.abs_diff_eq(Quat::IDENTITY, 1e-5));
+        assert!(mirrored[2]
+            .rotation
+            .abs_diff_eq(Quat::from_rotation_y(-0.5), 1e-5));
+    }
+
+    #[test]
+    fn mirror_pose_twice_is_the_original() {
+        let skeleton = skeleton();
+        let pose = [
+            Transform::from_xyz(0.5, 1.0, 0.0).with_rotation(Quat::from_rotation_z(0.3)),
+            Transform::from_rotation(Quat::from_euler(EulerRot::XYZ, 0.1, 0.2, 0.3)),
+            Transform::from_rotation(Quat::from_rotation_x(-0.7)),
+        ];
+        let twice = mirror_pose(&skeleton, &mirror_pose(&skeleton, &pose));
+        for (original, twice) in pose.iter().zip(&twice) {
+            assert!(original.translation.abs_diff_eq(twice.translation, 1e-5));
+            assert!(original.rotation.abs_diff_eq(twice.rotation, 1e-5));
+        }
+    }
+}
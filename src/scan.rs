@@ -0,0 +1,176 @@
+//! `--animations-dir <dir>` builds the animation list from every glTF in a
+//! folder under the asset folder instead of `animations.ron`. `--gltf <file>`
+//! takes both the character and its clips from one file, the way most
+//! exporters bundle them.
+//!
+//! How many clips a file holds is only known once it has loaded, so the files
+//! are listed first and the list is built once they have all loaded; `setup`
+//! waits until then.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use bevy::asset::LoadState;
+use bevy::gltf::Gltf;
+use bevy::prelude::*;
+
+use crate::{arg_value, AnimationParams, AnimationsMetadata};
+
+#[derive(Resource, Debug)]
+pub struct AnimationDirectoryScan {
+    /// Folder to scan, or a single glTF file, relative to the asset folder.
+    pub directory: String,
+    pending: Vec<(String, Handle<Gltf>)>,
+}
+
+impl AnimationDirectoryScan {
+    pub fn from_args() -> Option<Self> {
+        Some(Self {
+            directory: arg_value("--animations-dir")?,
+            pending: Vec::new(),
+        })
+    }
+
+    /// Lists the clips of the one file `file`.
+    pub fn single_file(file: &str) -> Self {
+        Self {
+            directory: file.to_string(),
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl AnimationsMetadata {
+    /// Asset paths of the `.glb` / `.gltf` files in `dir`, which is relative
+    /// to the asset folder, sorted by name. A file rather than a folder is
+    /// returned as is.
+    pub fn gltf_files(dir: &str) -> io::Result<Vec<String>> {
+        if Path::new("assets").join(dir).is_file() {
+            return Ok(vec![dir.to_string()]);
+        }
+        let dir = dir.trim_end_matches('/');
+        let mut files: Vec<String> = fs::read_dir(Path::new("assets").join(dir))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension().is_some_and(|ext| {
+                    ext.eq_ignore_ascii_case("glb") || ext.eq_ignore_ascii_case("gltf")
+                })
+            })
+            .filter_map(|path| Some(path.file_name()?.to_string_lossy().into_owned()))
+            .map(|file| {
+                if dir.is_empty() {
+                    file
+                } else {
+                    format!("{dir}/{file}")
+                }
+            })
+            .collect();
+        files.sort();
+        Ok(files)
+    }
+
+    /// One entry per clip of each loaded file, named after the file and the
+    /// clip's glTF name, or its index when it has none.
+    pub fn from_gltfs(files: &[(&str, &Gltf)]) -> Self {
+        let mut list = Vec::new();
+        for (file, gltf) in files {
+            let stem = Path::new(file).file_stem().map_or_else(
+                || file.to_string(),
+                |stem| stem.to_string_lossy().into_owned(),
+            );
+            for (index, handle) in gltf.animations.iter().enumerate() {
+                let name = gltf
+                    .named_animations
+                    .iter()
+                    .find(|(_, named)| *named == handle)
+                    .map_or_else(
+                        || format!("{stem} {index}"),
+                        |(name, _)| format!("{stem}/{name}"),
+                    );
+                list.push(AnimationParams::new(
+                    &format!("{file}#Animation{index}"),
+                    &name,
+                ));
+            }
+        }
+        AnimationsMetadata(list)
+    }
+}
+
+pub struct AnimationDirectoryPlugin;
+
+impl Plugin for AnimationDirectoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            scan_animation_directory.run_if(resource_exists::<AnimationDirectoryScan>()),
+        );
+    }
+}
+
+fn scan_animation_directory(
+    mut commands: Commands,
+    mut scan: ResMut<AnimationDirectoryScan>,
+    asset_server: Res<AssetServer>,
+    gltfs: Res<Assets<Gltf>>,
+    mut animation_meta: ResMut<AnimationsMetadata>,
+) {
+    if scan.pending.is_empty() {
+        match AnimationsMetadata::gltf_files(&scan.directory) {
+            Ok(files) if !files.is_empty() => {
+                println!("scanning {} file(s) in {}", files.len(), scan.directory);
+                scan.pending = files
+                    .into_iter()
+                    .map(|file| {
+                        let handle = asset_server.load(file.clone());
+                        (file, handle)
+                    })
+                    .collect();
+            }
+            Ok(_) => {
+                println!(
+                    "no glTF files in {}, keeping the animation list",
+                    scan.directory
+                );
+                commands.remove_resource::<AnimationDirectoryScan>();
+            }
+            Err(e) => {
+                println!("could not scan {}: {e}", scan.directory);
+                commands.remove_resource::<AnimationDirectoryScan>();
+            }
+        }
+        return;
+    }
+
+    let still_loading = scan.pending.iter().any(|(_, handle)| {
+        !matches!(
+            asset_server.get_load_state(handle.id()),
+            Some(LoadState::Loaded | LoadState::Failed)
+        )
+    });
+    if still_loading {
+        return;
+    }
+
+    let loaded: Vec<(&str, &Gltf)> = scan
+        .pending
+        .iter()
+        .filter_map(|(file, handle)| Some((file.as_str(), gltfs.get(handle)?)))
+        .collect();
+    let scanned = AnimationsMetadata::from_gltfs(&loaded);
+    if scanned.0.is_empty() {
+        println!(
+            "no animations in {}, keeping the animation list",
+            scan.directory
+        );
+    } else {
+        println!(
+            "found {} animation(s) in {}",
+            scanned.0.len(),
+            scan.directory
+        );
+        *animation_meta = scanned;
+    }
+    commands.remove_resource::<AnimationDirectoryScan>();
+}
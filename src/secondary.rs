@@ -0,0 +1,161 @@
+//! Spring-driven secondary motion layered over the playing clip.
+//!
+//! Each configured chain is a run of bones from parent to child. The tip of
+//! every link is simulated as a verlet particle pulled toward its animated
+//! position; the link's bone is then rotated to point at the particle. This
+//! gives a cheap preview of follow-through on hair, cloth or tails.
+
+use bevy::animation::animation_player;
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+use bevy::utils::HashMap;
+
+use crate::skeleton::Skeleton;
+use crate::ui::keyboard_free;
+
+#[derive(Debug, Clone)]
+pub struct SpringChain {
+    /// Bone names from the chain root to its tip; each must be the parent of
+    /// the next.
+    pub bones: Vec<String>,
+    /// Fraction of the distance to the animated position recovered per frame.
+    pub stiffness: f32,
+    /// Fraction of velocity lost per frame.
+    pub damping: f32,
+}
+
+#[derive(Resource, Debug)]
+pub struct SecondaryMotionConfig {
+    pub enabled: bool,
+    pub chains: Vec<SpringChain>,
+}
+
+impl Default for SecondaryMotionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            chains: vec![SpringChain {
+                bones: vec![
+                    "mixamorig:Neck".to_string(),
+                    "mixamorig:Head".to_string(),
+                    "mixamorig:HeadTop_End".to_string(),
+                ],
+                stiffness: 0.2,
+                damping: 0.15,
+            }],
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Particle {
+    position: Vec3,
+    previous: Vec3,
+}
+
+/// Simulated link tips, keyed by (chain index, link index).
+#[derive(Resource, Default)]
+struct SpringState(HashMap<(usize, usize), Particle>);
+
+pub struct SecondaryMotionPlugin;
+
+impl Plugin for SecondaryMotionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SecondaryMotionConfig>()
+            .init_resource::<SpringState>()
+            .add_systems(Update, toggle_secondary_motion.run_if(keyboard_free))
+            .add_systems(
+                PostUpdate,
+                simulate_springs
+                    .run_if(|config: Res<SecondaryMotionConfig>| config.enabled)
+                    .after(animation_player)
+                    .before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+fn toggle_secondary_motion(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut config: ResMut<SecondaryMotionConfig>,
+    mut state: ResMut<SpringState>,
+) {
+    if keyboard_input.just_pressed(KeyCode::J) {
+        config.enabled = !config.enabled;
+        state.0.clear();
+        println!("secondary motion: {}", config.enabled);
+    }
+}
+
+fn simulate_springs(
+    config: Res<SecondaryMotionConfig>,
+    skeleton: Res<Skeleton>,
+    mut state: ResMut<SpringState>,
+    parents: Query<&Parent>,
+    global_transforms: Query<&GlobalTransform>,
+    mut transforms: Query<&mut Transform>,
+) {
+    for (chain_index, chain) in config.chains.iter().enumerate() {
+        let Some(indices) = chain
+            .bones
+            .iter()
+            .map(|name| skeleton.index_of(name))
+            .collect::<Option<Vec<_>>>()
+        else {
+            continue;
+        };
+        let is_chain = indices
+            .windows(2)
+            .all(|pair| skeleton.bones[pair[1]].parent == Some(pair[0]));
+        if indices.len() < 2 || !is_chain {
+            continue;
+        }
+
+        let entities: Vec<Entity> = indices.iter().map(|&i| skeleton.bones[i].entity).collect();
+
+        // Last frame's world transform of whatever the chain hangs from; the
+        // chain itself is re-posed from this frame's freshly sampled locals.
+        let Some(mut parent_world) = parents
+            .get(entities[0])
+            .ok()
+            .and_then(|parent| global_transforms.get(parent.get()).ok())
+            .map(|global| global.compute_transform())
+        else {
+            continue;
+        };
+
+        for link in 0..entities.len() - 1 {
+            let Ok(local) = transforms.get(entities[link]).copied() else {
+                break;
+            };
+            let Ok(child_local) = transforms.get(entities[link + 1]).copied() else {
+                break;
+            };
+            let world = parent_world.mul_transform(local);
+            let animated_tip = world.mul_transform(child_local).translation;
+            let length = (animated_tip - world.translation).length();
+
+            let particle = state.0.entry((chain_index, link)).or_insert(Particle {
+                position: animated_tip,
+                previous: animated_tip,
+            });
+            let velocity = (particle.position - particle.previous) * (1.0 - chain.damping);
+            let mut next =
+                particle.position + velocity + (animated_tip - particle.position) * chain.stiffness;
+            next = world.translation + (next - world.translation).normalize_or_zero() * length;
+            particle.previous = particle.position;
+            particle.position = next;
+
+            let swing = Quat::from_rotation_arc(
+                (animated_tip - world.translation).normalize_or_zero(),
+                (next - world.translation).normalize_or_zero(),
+            );
+            let mut new_local = local;
+            new_local.rotation =
+                (parent_world.rotation.inverse() * swing * world.rotation).normalize();
+            if let Ok(mut transform) = transforms.get_mut(entities[link]) {
+                transform.rotation = new_local.rotation;
+            }
+            parent_world = parent_world.mul_transform(new_local);
+        }
+    }
+}
@@ -0,0 +1,191 @@
+//! The bone hierarchy of the loaded character, gathered once the scene spawns.
+
+use bevy::animation::EntityPath;
+use bevy::hierarchy::HierarchyQueryExt;
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+
+use crate::ModelRoot;
+
+#[derive(Debug, Clone)]
+pub struct Bone {
+    pub entity: Entity,
+    pub name: String,
+    /// Index of the closest ancestor that is also a bone.
+    pub parent: Option<usize>,
+    /// Names from the animation player down to this bone, as used by clip curves.
+    pub path: EntityPath,
+    /// Local transform when the scene spawned, before any clip was applied.
+    pub rest: Transform,
+}
+
+/// Bones of the character driven by the `AnimationPlayer`, parents before
+/// children.
+#[derive(Resource, Default, Debug)]
+pub struct Skeleton {
+    pub root: Option<Entity>,
+    pub bones: Vec<Bone>,
+    /// Paths of every named node under the root, bones or not. Clip curves may
+    /// target any of these.
+    pub node_paths: HashSet<EntityPath>,
+}
+
+impl Skeleton {
+    /// Looks a bone up by its full name, or by the part after a rig prefix
+    /// such as `mixamorig:`, so configs can say `LeftFoot`.
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.bones
+            .iter()
+            .position(|bone| bone.name == name)
+            .or_else(|| {
+                self.bones
+                    .iter()
+                    .position(|bone| bone.name.rsplit(':').next() == Some(name))
+            })
+    }
+
+    pub fn find(&self, name: &str) -> Option<&Bone> {
+        self.index_of(name).map(|index| &self.bones[index])
+    }
+
+    /// The bone on the other side of the body, found by swapping `Left` and
+    /// `Right` in the name; bones on the midline map to themselves.
+    pub fn mirror_of(&self, index: usize) -> usize {
+        let name = &self.bones[index].name;
+        let mirrored = if name.contains("Left") {
+            name.replace("Left", "Right")
+        } else {
+            name.replace("Right", "Left")
+        };
+        self.bones
+            .iter()
+            .position(|bone| bone.name == mirrored)
+            .unwrap_or(index)
+    }
+
+    pub fn children_of(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        self.bones
+            .iter()
+            .enumerate()
+            .filter(move |(_, bone)| bone.parent == Some(index))
+            .map(|(i, _)| i)
+    }
+}
+
+/// Size of the loaded character, to catch over-detailed exports.
+#[derive(Resource, Default, Debug)]
+pub struct SceneStats {
+    pub bones: usize,
+    /// Mesh entities; a mesh drawn twice counts twice.
+    pub meshes: usize,
+    pub vertices: usize,
+}
+
+pub struct SkeletonPlugin;
+
+impl Plugin for SkeletonPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Skeleton>()
+            .init_resource::<SceneStats>()
+            .add_systems(Update, (collect_skeleton, collect_scene_stats).chain());
+    }
+}
+
+fn collect_skeleton(
+    players: Query<Entity, Added<AnimationPlayer>>,
+    children: Query<&Children>,
+    names: Query<&Name>,
+    transforms: Query<&Transform>,
+    skinned_meshes: Query<&SkinnedMesh>,
+    mut skeleton: ResMut<Skeleton>,
+) {
+    let Some(root) = players.iter().next() else {
+        return;
+    };
+
+    // Skin joints are the real bones; mesh and armature nodes share the
+    // hierarchy but shouldn't be drawn or sampled as bones.
+    let joints: HashSet<Entity> = children
+        .iter_descendants(root)
+        .filter_map(|entity| skinned_meshes.get(entity).ok())
+        .flat_map(|skinned| skinned.joints.iter().copied())
+        .collect();
+
+    let mut bones = Vec::new();
+    let mut node_paths = HashSet::new();
+    let root_path = EntityPath {
+        parts: names.get(root).into_iter().cloned().collect(),
+    };
+    node_paths.insert(root_path.clone());
+    let mut stack = vec![(root, None, root_path)];
+    while let Some((entity, parent, path)) = stack.pop() {
+        let Ok(entity_children) = children.get(entity) else {
+            continue;
+        };
+        for &child in entity_children {
+            let Ok(name) = names.get(child) else {
+                continue;
+            };
+            let mut child_path = path.clone();
+            child_path.parts.push(name.clone());
+            node_paths.insert(child_path.clone());
+
+            let is_bone = joints.is_empty() || joints.contains(&child);
+            let child_parent = if is_bone {
+                bones.push(Bone {
+                    entity: child,
+                    name: name.as_str().to_string(),
+                    parent,
+                    path: child_path.clone(),
+                    rest: transforms.get(child).copied().unwrap_or_default(),
+                });
+                Some(bones.len() - 1)
+            } else {
+                parent
+            };
+            stack.push((child, child_parent, child_path));
+        }
+    }
+
+    println!("skeleton: {} bones", bones.len());
+    *skeleton = Skeleton {
+        root: Some(root),
+        bones,
+        node_paths,
+    };
+}
+
+/// Counts the character's meshes and vertices once its scene has spawned. The
+/// skinned meshes share one skeleton, so bones are the skeleton's joints
+/// rather than a count per mesh.
+fn collect_scene_stats(
+    players: Query<(), Added<AnimationPlayer>>,
+    roots: Query<Entity, With<ModelRoot>>,
+    children: Query<&Children>,
+    mesh_handles: Query<&Handle<Mesh>>,
+    meshes: Res<Assets<Mesh>>,
+    skeleton: Res<Skeleton>,
+    mut stats: ResMut<SceneStats>,
+) {
+    if players.is_empty() {
+        return;
+    }
+    let handles: Vec<&Handle<Mesh>> = roots
+        .iter()
+        .flat_map(|root| children.iter_descendants(root))
+        .filter_map(|entity| mesh_handles.get(entity).ok())
+        .collect();
+    *stats = SceneStats {
+        bones: skeleton.bones.len(),
+        meshes: handles.len(),
+        vertices: handles
+            .iter()
+            .filter_map(|handle| meshes.get(*handle))
+            .map(|mesh| mesh.count_vertices())
+            .sum(),
+    };
+    println!(
+        "scene: {} bones, {} meshes, {} vertices",
+        stats.bones, stats.meshes, stats.vertices
+    );
+}
@@ -0,0 +1,168 @@
+//! egui setup and the settings shared by all of the tool's panels.
+
+use std::fs;
+use std::io;
+
+use bevy::prelude::*;
+use bevy_inspector_egui::bevy_egui::{EguiContexts, EguiPlugin, EguiSet, EguiSettings};
+use bevy_inspector_egui::egui;
+use serde::{Deserialize, Serialize};
+
+use crate::arg_value;
+
+pub const UI_SETTINGS_PATH: &str = "assets/ui_settings.ron";
+
+const UI_SCALE_STEP: f32 = 0.1;
+const UI_SCALE_MIN: f32 = 0.5;
+const UI_SCALE_MAX: f32 = 3.0;
+
+/// Settings for all panels. The scale is kept in `assets/ui_settings.ron`
+/// across sessions; the dock comes from `--dock` or the project file.
+#[derive(Resource, Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct UiSettings {
+    /// Multiplier applied on top of the window's own scale factor.
+    pub scale: f32,
+    #[serde(skip)]
+    pub dock: UiDock,
+    /// File a changed scale is saved to; `None` when the scale came from a
+    /// project file, or the settings file failed to parse.
+    #[serde(skip)]
+    pub file: Option<String>,
+}
+
+impl Default for UiSettings {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            dock: UiDock::Top,
+            file: None,
+        }
+    }
+}
+
+/// `scale` limited to the range =/- step through, since egui breaks down at
+/// zero or negative scales.
+pub fn clamp_ui_scale(scale: f32) -> f32 {
+    scale.clamp(UI_SCALE_MIN, UI_SCALE_MAX)
+}
+
+/// Edge of the window the HUD and the timeline sit against, e.g. the top
+/// when the feet are at the bottom of the view. Set with `--dock top|bottom`
+/// or `ui_dock` in a project file.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UiDock {
+    #[default]
+    Top,
+    Bottom,
+}
+
+impl UiSettings {
+    pub fn from_file(path: &str) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        ron::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(path, text)
+    }
+}
+
+impl UiDock {
+    pub fn from_args() -> Option<Self> {
+        match arg_value("--dock")?.as_str() {
+            "top" => Some(UiDock::Top),
+            "bottom" => Some(UiDock::Bottom),
+            other => {
+                println!("error: --dock {other}: expected top or bottom");
+                None
+            }
+        }
+    }
+
+    /// Where a window centered on the docked edge is anchored.
+    pub fn anchor(self) -> (egui::Align2, egui::Vec2) {
+        match self {
+            UiDock::Top => (egui::Align2::CENTER_TOP, egui::vec2(0.0, 8.0)),
+            UiDock::Bottom => (egui::Align2::CENTER_BOTTOM, egui::vec2(0.0, -8.0)),
+        }
+    }
+}
+
+/// Whether an egui widget has the keyboard, e.g. a text field being typed
+/// in. Refreshed at the start of every frame for `keyboard_free`.
+#[derive(Resource, Default, Debug)]
+pub struct EguiKeyboardFocus(pub bool);
+
+/// Run condition for keyboard shortcuts: false while egui wants the keys, so
+/// typing a filter or a preset name doesn't also drive the viewer.
+pub fn keyboard_free(focus: Res<EguiKeyboardFocus>) -> bool {
+    !focus.0
+}
+
+pub struct UiPlugin;
+
+impl Plugin for UiPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<EguiPlugin>() {
+            app.add_plugins(EguiPlugin);
+        }
+        let settings = match UiSettings::from_file(UI_SETTINGS_PATH) {
+            Ok(settings) => UiSettings {
+                scale: clamp_ui_scale(settings.scale),
+                file: Some(UI_SETTINGS_PATH.to_string()),
+                ..settings
+            },
+            Err(e) if e.kind() == io::ErrorKind::NotFound => UiSettings {
+                file: Some(UI_SETTINGS_PATH.to_string()),
+                ..default()
+            },
+            Err(e) => {
+                println!("ui settings: could not load {UI_SETTINGS_PATH}: {e}");
+                UiSettings::default()
+            }
+        };
+        app.insert_resource(settings)
+            .init_resource::<EguiKeyboardFocus>()
+            .add_systems(PreUpdate, track_keyboard_focus.after(EguiSet::BeginFrame))
+            .add_systems(
+                Update,
+                (
+                    keyboard_ui_scale.run_if(keyboard_free),
+                    apply_ui_scale.run_if(resource_changed::<UiSettings>()),
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn track_keyboard_focus(mut contexts: EguiContexts, mut focus: ResMut<EguiKeyboardFocus>) {
+    let wants_keyboard = contexts.ctx_mut().wants_keyboard_input();
+    if focus.0 != wants_keyboard {
+        focus.0 = wants_keyboard;
+    }
+}
+
+fn keyboard_ui_scale(keyboard_input: Res<Input<KeyCode>>, mut settings: ResMut<UiSettings>) {
+    let step = if keyboard_input.just_pressed(KeyCode::Equals) {
+        UI_SCALE_STEP
+    } else if keyboard_input.just_pressed(KeyCode::Minus) {
+        -UI_SCALE_STEP
+    } else {
+        return;
+    };
+    settings.scale = clamp_ui_scale(settings.scale + step);
+    println!("ui scale: {:.1}", settings.scale);
+    let Some(path) = settings.file.clone() else {
+        return;
+    };
+    if let Err(e) = settings.save(&path) {
+        println!("could not write {path}: {e}");
+    }
+}
+
+fn apply_ui_scale(settings: Res<UiSettings>, mut egui_settings: ResMut<EguiSettings>) {
+    egui_settings.scale_factor = settings.scale as f64;
+}